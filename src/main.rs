@@ -2,6 +2,7 @@
 mod utils;
 mod model;
 mod network;
+mod save;
 mod view;
 
 pub const NB: i32 = 12;
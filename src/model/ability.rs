@@ -0,0 +1,80 @@
+// the special action each class of boat grants while it is alive,
+// used instead of attacking
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Ability {
+    AirStrike, // attacks a 3x3 square, once per game
+    Barrage,   // attacks a row of 3 cells
+    Torpedo,   // attacks a column of 3 cells
+    Sonar,     // reveals the occupied cells of a 3x3 square
+    Flare,     // reveals the occupied cells of a cross
+}
+
+// the cooldown of an ability which can't be used anymore
+pub const USED: u8 = u8::MAX;
+
+impl Ability {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Ability::AirStrike => "AirStrike",
+            Ability::Barrage => "Barrage",
+            Ability::Torpedo => "Torpedo",
+            Ability::Sonar => "Sonar",
+            Ability::Flare => "Flare",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "airstrike" => Some(Ability::AirStrike),
+            "barrage" => Some(Ability::Barrage),
+            "torpedo" => Some(Ability::Torpedo),
+            "sonar" => Some(Ability::Sonar),
+            "flare" => Some(Ability::Flare),
+            _ => None,
+        }
+    }
+
+    // whether the targeted cells are attacked, or only revealed
+    pub fn attacks(&self) -> bool {
+        match self {
+            Ability::AirStrike | Ability::Barrage | Ability::Torpedo => true,
+            Ability::Sonar | Ability::Flare => false,
+        }
+    }
+
+    // number of turns of its owner before it can be used again
+    pub fn cooldown(&self) -> u8 {
+        match self {
+            Ability::AirStrike => USED,
+            Ability::Barrage | Ability::Torpedo => 3,
+            Ability::Sonar => 4,
+            Ability::Flare => 2,
+        }
+    }
+
+    // the cells of the board targeted when aiming at the given position
+    pub fn area(&self, p: (u8, u8), size: (usize, usize)) -> Vec<(u8, u8)> {
+        let offsets: &[(i8, i8)] = match self {
+            Ability::AirStrike | Ability::Sonar => &[
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+                (-1, 0),
+                (0, 0),
+                (1, 0),
+                (-1, 1),
+                (0, 1),
+                (1, 1),
+            ],
+            Ability::Barrage => &[(-1, 0), (0, 0), (1, 0)],
+            Ability::Torpedo => &[(0, -1), (0, 0), (0, 1)],
+            Ability::Flare => &[(0, -1), (-1, 0), (0, 0), (1, 0), (0, 1)],
+        };
+        offsets
+            .iter()
+            .map(|(dx, dy)| (p.0 as i8 + dx, p.1 as i8 + dy))
+            .filter(|&(x, y)| in_board!(x, y, size.0, size.1))
+            .map(|(x, y)| (x as u8, y as u8))
+            .collect()
+    }
+}
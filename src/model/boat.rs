@@ -1,7 +1,7 @@
 type Position = (u8, u8);
-use crate::model::direction::Direction;
+use crate::model::{ability::Ability, direction::Direction};
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Class {
     Carrier,
     Battleship,
@@ -10,6 +10,15 @@ pub enum Class {
     Destroyer,
 }
 
+// the boats each player has
+pub const FLEET: [Class; 5] = [
+    Class::Carrier,
+    Class::Battleship,
+    Class::Cruiser,
+    Class::Submarine,
+    Class::Destroyer,
+];
+
 impl Class {
     pub fn max_life(&self) -> u8 {
         match self {
@@ -20,14 +29,123 @@ impl Class {
             Class::Destroyer => 2,
         }
     }
+
+    pub fn ability(&self) -> Ability {
+        match self {
+            Class::Carrier => Ability::AirStrike,
+            Class::Battleship => Ability::Barrage,
+            Class::Cruiser => Ability::Torpedo,
+            Class::Submarine => Ability::Sonar,
+            Class::Destroyer => Ability::Flare,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Class::Carrier => "Carrier",
+            Class::Battleship => "Battleship",
+            Class::Cruiser => "Cruiser",
+            Class::Submarine => "Submarine",
+            Class::Destroyer => "Destroyer",
+        }
+    }
+
+    // the hull of the boats of this class, with the shaped hulls rule or not
+    pub fn hull(&self, shaped: bool) -> Hull {
+        match self {
+            Class::Carrier if shaped => Hull::T,
+            Class::Battleship if shaped => Hull::Square,
+            Class::Submarine if shaped => Hull::L,
+            _ => Hull::Line,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "carrier" => Some(Class::Carrier),
+            "battleship" => Some(Class::Battleship),
+            "cruiser" => Some(Class::Cruiser),
+            "submarine" => Some(Class::Submarine),
+            "destroyer" => Some(Class::Destroyer),
+            _ => None,
+        }
+    }
+}
+
+// the shape of a boat, given by the positions of its segments when it faces right
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Hull {
+    Line,   // every segment in a row
+    L,      // a row with the last segment bent down
+    T,      // a row of 3 segments with the other ones below its middle
+    Square, // 2 rows of 2 segments
+}
+
+impl Hull {
+    // the positions of the segments from the first one, forward along x and to the right along y
+    pub fn offsets(&self, len: u8) -> Vec<(i8, i8)> {
+        let len = len as i8;
+        match self {
+            Hull::Line => (0..len).map(|i| (i, 0)).collect(),
+            Hull::L => (0..len - 1)
+                .map(|i| (i, 0))
+                .chain(vec![(len - 2, 1)])
+                .collect(),
+            Hull::T => (0..3)
+                .map(|i| (i, 0))
+                .chain((1..len - 2).map(|i| (1, i)))
+                .collect(),
+            Hull::Square => (0..len).map(|i| (i / 2, i % 2)).collect(),
+        }
+    }
+
+    // the number of rows of the hull
+    pub fn depth(&self, len: u8) -> u8 {
+        self.offsets(len)
+            .iter()
+            .map(|&(_, y)| y as u8 + 1)
+            .max()
+            .unwrap_or(1)
+    }
+}
+
+// the ways to move an undamaged boat during a game
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Movement {
+    Forward,  // one cell in its direction
+    Backward, // one cell in the opposite direction
+    Rotate,   // a quarter turn around its first segment
+}
+
+pub const MOVEMENTS: [Movement; 3] = [Movement::Forward, Movement::Backward, Movement::Rotate];
+
+impl Movement {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Movement::Forward => "Forward",
+            Movement::Backward => "Backward",
+            Movement::Rotate => "Rotate",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "forward" => Some(Movement::Forward),
+            "backward" => Some(Movement::Backward),
+            "rotate" => Some(Movement::Rotate),
+            _ => None,
+        }
+    }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Boat {
     pub class: Class,
     pub position: Position,
     pub direction: Direction,
+    pub hull: Hull, // given by the rules of the game the boat is added to
     pub detailed_life: Vec<bool>,
+    pub cooldown: u8, // turns before its ability can be used again
 }
 
 impl Boat {
@@ -45,7 +163,9 @@ impl Boat {
             class,
             position,
             direction,
+            hull: Hull::Line,
             detailed_life: vec![true; class.max_life() as usize],
+            cooldown: 0,
         }
     }
 
@@ -53,30 +173,44 @@ impl Boat {
         self.class.max_life()
     }
 
-    pub fn shoot(&mut self, position: Position) -> bool {
-        let n: i8;
+    pub fn is_damaged(&self) -> bool {
+        self.detailed_life.iter().any(|b| !*b)
+    }
 
-        if self.direction.dx() == 0 {
-            if self.position.0 == position.0 && self.direction.dy() != 0 {
-                n = (position.1 as i8 - self.position.1 as i8) / self.direction.dy();
-            } else {
-                return false;
-            }
-        } else if self.direction.dy() == 0 {
-            if self.position.1 == position.1 {
-                n = (position.0 as i8 - self.position.0 as i8) / self.direction.dx();
-            } else {
-                return false;
-            }
-        } else {
-            return false;
-        }
+    // the positions of the segments of the boat, they may be out of the board
+    pub fn cells(&self) -> Vec<(i8, i8)> {
+        self.hull_cells(self.hull)
+    }
+
+    // the positions of the segments if the boat had the given hull
+    pub fn hull_cells(&self, hull: Hull) -> Vec<(i8, i8)> {
+        let (x, y) = (self.position.0 as i8, self.position.1 as i8);
+        self.offsets(hull)
+            .into_iter()
+            .map(|(dx, dy)| (x + dx, y + dy))
+            .collect()
+    }
 
-        if n < 0 || n >= self.max_life() as i8 || !self.detailed_life[n as usize] {
+    // the positions of the segments from the first one, the hull turned towards the direction
+    pub fn offsets(&self, hull: Hull) -> Vec<(i8, i8)> {
+        let (dx, dy) = self.direction.delta();
+        hull.offsets(self.max_life())
+            .into_iter()
+            .map(|(a, b)| (a * dx - b * dy, a * dy + b * dx))
+            .collect()
+    }
+
+    pub fn shoot(&mut self, position: Position) -> bool {
+        let target = (position.0 as i8, position.1 as i8);
+        let n = match self.cells().iter().position(|&c| c == target) {
+            Some(n) => n,
+            None => return false,
+        };
+        if !self.detailed_life[n] {
             return false;
         }
 
-        self.detailed_life[n as usize] = false;
+        self.detailed_life[n] = false;
         return true;
     }
 }
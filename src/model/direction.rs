@@ -1,30 +1,111 @@
-#[derive(Copy, Clone, Debug)]
+// a direction is encoded as dx * 3 + dy
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Direction {
     Up = 1,
     Right = 3,
     Down = -1,
     Left = -3,
+    UpRight = 4,
+    DownRight = 2,
+    DownLeft = -4,
+    UpLeft = -2,
 }
 
+pub const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Right,
+    Direction::Down,
+    Direction::Left,
+];
+
+// the directions of the boats placed with the diagonal boats rule
+pub const DIAGONALS: [Direction; 4] = [
+    Direction::UpRight,
+    Direction::DownRight,
+    Direction::DownLeft,
+    Direction::UpLeft,
+];
+
 impl Direction {
+    // rounded to the nearest multiple of 3, since dy may be negative
     pub fn dx(&self) -> i8 {
-        (*self as i8) / 3
+        let code = *self as i8;
+        (code + code.signum()) / 3
     }
 
     pub fn dy(&self) -> i8 {
-        (*self as i8) % 3
+        *self as i8 - 3 * self.dx()
+    }
+
+    pub fn is_diagonal(&self) -> bool {
+        self.dx() != 0 && self.dy() != 0
+    }
+
+    // the directions allowed by the rules, used to encode the directions of the network messages
+    pub fn all(diagonal: bool) -> Vec<Self> {
+        let mut directions = DIRECTIONS.to_vec();
+        if diagonal {
+            directions.extend_from_slice(&DIAGONALS);
+        }
+        directions
     }
 
     pub fn delta(&self) -> (i8, i8) {
         (self.dx(), self.dy())
     }
 
+    pub fn name(&self) -> &'static str {
+        match self {
+            Direction::Up => "Up",
+            Direction::Right => "Right",
+            Direction::Down => "Down",
+            Direction::Left => "Left",
+            Direction::UpRight => "Up-right",
+            Direction::DownRight => "Down-right",
+            Direction::DownLeft => "Down-left",
+            Direction::UpLeft => "Up-left",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "up" => Some(Direction::Up),
+            "right" => Some(Direction::Right),
+            "down" => Some(Direction::Down),
+            "left" => Some(Direction::Left),
+            "up-right" => Some(Direction::UpRight),
+            "down-right" => Some(Direction::DownRight),
+            "down-left" => Some(Direction::DownLeft),
+            "up-left" => Some(Direction::UpLeft),
+            _ => None,
+        }
+    }
+
+    // a quarter turn
     pub fn rotate(&self) -> Self {
         match self {
             Direction::Up => Direction::Left,
             Direction::Right => Direction::Up,
             Direction::Down => Direction::Right,
             Direction::Left => Direction::Down,
+            Direction::UpRight => Direction::UpLeft,
+            Direction::DownRight => Direction::UpRight,
+            Direction::DownLeft => Direction::DownRight,
+            Direction::UpLeft => Direction::DownLeft,
+        }
+    }
+
+    // an eighth of a turn, in the same way as a quarter turn
+    pub fn rotate_diagonal(&self) -> Self {
+        match self {
+            Direction::Up => Direction::UpLeft,
+            Direction::UpLeft => Direction::Left,
+            Direction::Left => Direction::DownLeft,
+            Direction::DownLeft => Direction::Down,
+            Direction::Down => Direction::DownRight,
+            Direction::DownRight => Direction::Right,
+            Direction::Right => Direction::UpRight,
+            Direction::UpRight => Direction::Up,
         }
     }
 }
@@ -1,11 +1,20 @@
 use crate::model::{
-    boat::{Boat, Class},
+    ability::USED,
+    boat::{Boat, Class, Movement, FLEET},
     direction::Direction,
+    notation::{
+        format_ability, format_attack, format_movement, format_position, format_positions,
+        format_reveal,
+    },
+    party::Party,
     player::Player,
+    replay::Replay,
+    rules::Rules,
+    terrain::{Map, Terrain, MINES},
 };
-use crate::network::{ATTACK, CONFIRM};
+use crate::network::{ABILITY, ATTACK, CONFIRM, MINE, MOVE, PARTY_ATTACK, REVEAL};
 use crate::NB;
-use rand::Rng;
+use rand::{seq::SliceRandom, Rng};
 use std::io::Write;
 
 #[macro_export]
@@ -14,15 +23,45 @@ macro_rules! game {
         match $x {
             GameType::Network { game, .. } => game,
             GameType::Ai { game, .. } => game,
+            GameType::Local { game, .. } => game,
+            GameType::Party { game, .. } => game,
         }
     };
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Shot {
+    Miss = 0,
+    Hit = 1,
+    Sunk = 2, // hit the last segment of a boat
+    Mine = 3, // missed, but a mine damages the fleet of the attacker
+}
+
+impl Shot {
+    // a shot on a mine is a miss
+    pub fn is_hit(&self) -> bool {
+        *self == Shot::Hit || *self == Shot::Sunk
+    }
+
+    pub fn from_byte(b: u8) -> Option<Self> {
+        match b {
+            0 => Some(Shot::Miss),
+            1 => Some(Shot::Hit),
+            2 => Some(Shot::Sunk),
+            3 => Some(Shot::Mine),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Game {
     pub board_boats: Vec<Vec<Option<u8>>>,
     pub shot_boats: Vec<Vec<Option<bool>>>,
+    pub revealed: Vec<Vec<bool>>, // positions known to be occupied by an opponent boat
+    pub terrain: Vec<Vec<Terrain>>, // the same as the board of the opponent, except the mines
     pub player: Player,
+    pub rules: Rules,
 }
 
 impl Game {
@@ -30,70 +69,454 @@ impl Game {
         self.shot_boats[p.0 as usize][p.1 as usize] != None
     }
 
-    pub fn create_ai_game() -> Result<Self, String> {
-        let carrier = Boat::new(Class::Carrier, (1, 0), Direction::Right);
-        let battleship = Boat::new(Class::Battleship, (0, 2), Direction::Up);
-        let cruiser = Boat::new(Class::Cruiser, (11, 1), Direction::Left);
-        let submarine = Boat::new(Class::Submarine, (10, 10), Direction::Down);
-        let destroyer = Boat::new(Class::Destroyer, (5, 5), Direction::Left);
+    pub fn island(&self, p: (u8, u8)) -> bool {
+        self.terrain[p.0 as usize][p.1 as usize] == Terrain::Island
+    }
 
-        let player = Player::new(vec![carrier, battleship, cruiser, submarine, destroyer]);
+    // whether the position is part of the board
+    pub fn playable(&self, p: (u8, u8)) -> bool {
+        self.terrain[p.0 as usize][p.1 as usize] != Terrain::Outside
+    }
 
-        Game::new(NB as usize, NB as usize, player)
+    // whether the position of the opponent can still be attacked
+    pub fn can_target(&self, p: (u8, u8)) -> bool {
+        !self.shot(p) && !self.island(p) && self.playable(p)
     }
 
-    pub fn confirm_attack(&mut self, p: (u8, u8), b: bool) {
-        self.shot_boats[p.0 as usize][p.1 as usize] = Some(b);
+    pub fn create_ai_game(map: &Map, rules: Rules) -> Result<Self, String> {
+        let mut game = Game::empty(map, rules);
+        game.add_random_fleet()?;
+        game.add_random_mines();
+        Ok(game)
     }
 
-    pub fn opponent_attack(&mut self, p: (u8, u8)) -> bool {
-        println!("game : opponent shot ({},{})", p.0, p.1);
+    // checks that a whole fleet can be placed in the map with the rules
+    pub fn fits(map: &Map, rules: Rules) -> Result<(), String> {
+        Game::empty(map, rules).add_random_fleet().map_err(|_| {
+            format!(
+                "The fleet doesn't fit in the map {} with these rules",
+                map.name
+            )
+        })
+    }
+
+    pub fn confirm_attack(&mut self, p: (u8, u8), shot: Shot) {
+        self.shot_boats[p.0 as usize][p.1 as usize] = Some(shot.is_hit());
+        if shot == Shot::Sunk && self.rules.no_touching {
+            // boats can't touch, so the cells around a sunk boat are empty
+            for (x, y) in self.hit_group(p) {
+                for (nx, ny) in self.neighbours(x as i8, y as i8) {
+                    if self.shot_boats[nx][ny] == None {
+                        self.shot_boats[nx][ny] = Some(false);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn opponent_attack(&mut self, p: (u8, u8)) -> Shot {
+        println!("game : opponent shot {}", format_position(p));
         match &self.board_boats[p.0 as usize][p.1 as usize] {
-            None => false,
-            Some(i) => self.player.boats[*i as usize].shoot(p),
+            None if self.terrain[p.0 as usize][p.1 as usize] == Terrain::Mine => {
+                println!("game : mine exploded on {}", format_position(p));
+                self.terrain[p.0 as usize][p.1 as usize] = Terrain::Water;
+                Shot::Mine
+            }
+            None => Shot::Miss,
+            Some(i) => {
+                let boat = &mut self.player.boats[*i as usize];
+                if !boat.shoot(p) {
+                    Shot::Miss
+                } else if boat.is_dead() {
+                    Shot::Sunk
+                } else {
+                    Shot::Hit
+                }
+            }
+        }
+    }
+
+    pub fn new(size_x: usize, size_y: usize, player: Player, rules: Rules) -> Result<Self, String> {
+        Game::with_map(&Map::new(size_x, size_y), player, rules)
+    }
+
+    // the board of the player as the opponent sees it, with a boat of each class
+    // which only follows the cooldown of the ability of the opponent's one
+    pub fn opponent_view(map: &Map, rules: Rules) -> Self {
+        let mut game = Game::empty(map, rules);
+        for class in FLEET.iter() {
+            game.player
+                .boats
+                .push(Boat::new(*class, (0, 0), Direction::Right));
+        }
+        game
+    }
+
+    // the game without any boat
+    pub fn empty(map: &Map, rules: Rules) -> Self {
+        let (size_x, size_y) = (map.terrain.len(), map.terrain[0].len());
+        Game {
+            board_boats: vec![vec![None; size_y]; size_x],
+            shot_boats: vec![vec![None; size_y]; size_x],
+            revealed: vec![vec![false; size_y]; size_x],
+            terrain: map.board(rules.obstacles),
+            player: Player::new(Vec::with_capacity(FLEET.len())),
+            rules,
+        }
+    }
+
+    pub fn with_map(map: &Map, player: Player, rules: Rules) -> Result<Self, String> {
+        let mut game = Game::empty(map, rules);
+        for boat in player.boats {
+            game.add_boat(boat)?;
         }
+        Ok(game)
     }
 
-    pub fn new(size_x: usize, size_y: usize, player: Player) -> Result<Self, String> {
-        let mut board_boats: Vec<Vec<Option<u8>>> = vec![vec![None; size_y]; size_x];
+    // the positions around the given one which are in the board, diagonals included
+    fn neighbours(&self, x: i8, y: i8) -> Vec<(usize, usize)> {
+        let mut neighbours = Vec::with_capacity(8);
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                if (dx != 0 || dy != 0)
+                    && in_board!(
+                        x + dx,
+                        y + dy,
+                        self.board_boats.len(),
+                        self.board_boats[0].len()
+                    )
+                {
+                    neighbours.push(((x + dx) as usize, (y + dy) as usize));
+                }
+            }
+        }
+        neighbours
+    }
 
+    // the hit positions connected to the given one
+    fn hit_group(&self, p: (u8, u8)) -> Vec<(usize, usize)> {
+        let mut group = vec![(p.0 as usize, p.1 as usize)];
         let mut i = 0;
-        for boat in &player.boats {
-            let d = boat.direction.delta();
-            let mut x = boat.position.0 as i8;
-            let mut y = boat.position.1 as i8;
-
-            if !in_board!(x, y, board_boats.len(), board_boats[0].len())
-                || !in_board!(
-                    x as i8 + d.0 * (boat.max_life() - 1) as i8,
-                    y as i8 + d.1 * (boat.max_life() - 1) as i8,
-                    board_boats.len(),
-                    board_boats[0].len()
-                )
-            {
+        while i < group.len() {
+            let (x, y) = group[i];
+            for n in self.neighbours(x as i8, y as i8) {
+                if self.shot_boats[n.0][n.1] == Some(true) && !group.contains(&n) {
+                    group.push(n);
+                }
+            }
+            i += 1;
+        }
+        group
+    }
+
+    // checks that the boat can be added to the board
+    // the hull comes from the rules, whatever the one of the boat
+    pub fn check_boat(&self, boat: &Boat) -> Result<(), String> {
+        if boat.direction.is_diagonal() && !self.rules.diagonal_boats {
+            return Err(format!("Diagonal boats aren't allowed : {:?}", boat));
+        }
+        let cells = boat.hull_cells(boat.class.hull(self.rules.shaped_hulls));
+        for &(x, y) in &cells {
+            if !in_board!(x, y, self.board_boats.len(), self.board_boats[0].len()) {
                 return Err(format!("Wrong boat position {:?}", boat));
             }
-            for _ in 0..boat.max_life() {
-                match &board_boats[x as usize][y as usize] {
-                    Some(b) => {
+        }
+        for &(x, y) in &cells {
+            if let Some(b) = self.board_boats[x as usize][y as usize] {
+                return Err(format!(
+                    "Boat overleap : {:?} and {:?}",
+                    self.player.boats[b as usize], boat
+                ));
+            }
+            if self.island((x as u8, y as u8)) {
+                return Err(format!("Boat on an island : {:?}", boat));
+            } else if self.terrain[x as usize][y as usize] == Terrain::Mine {
+                // only possible when a boat is moved, the mines are hidden after the placement
+                return Err(format!("Boat on a mine : {:?}", boat));
+            } else if !self.playable((x as u8, y as u8)) {
+                return Err(format!("Boat out of the map : {:?}", boat));
+            }
+        }
+        // a boat can't cross another one between two of its segments in diagonal
+        for &(x, y) in &cells {
+            for &(xx, yy) in &cells {
+                if (xx - x).abs() != 1 || (yy - y).abs() != 1 {
+                    continue;
+                }
+                let a = self.board_boats[xx as usize][y as usize];
+                if a.is_some() && a == self.board_boats[x as usize][yy as usize] {
+                    return Err(format!(
+                        "Boats crossing : {:?} and {:?}",
+                        self.player.boats[a.unwrap() as usize],
+                        boat
+                    ));
+                }
+            }
+        }
+        if self.rules.no_touching {
+            for &(x, y) in &cells {
+                for (nx, ny) in self.neighbours(x, y) {
+                    if let Some(b) = self.board_boats[nx][ny] {
                         return Err(format!(
-                            "Boat overleap : {:?} and {:?}",
-                            player.boats[*b as usize], boat
-                        ))
+                            "Boats touching : {:?} and {:?}",
+                            self.player.boats[b as usize], boat
+                        ));
                     }
-                    None => board_boats[x as usize][y as usize] = Some(i),
                 }
-                x += d.0;
-                y += d.1;
             }
-            i += 1;
         }
+        Ok(())
+    }
 
-        Ok(Game {
-            board_boats,
-            shot_boats: vec![vec![None; size_y]; size_x],
-            player,
-        })
+    pub fn add_boat(&mut self, mut boat: Boat) -> Result<(), String> {
+        self.check_boat(&boat)?;
+        boat.hull = boat.class.hull(self.rules.shaped_hulls);
+        let i = self.player.boats.len() as u8;
+        for (x, y) in boat.cells() {
+            self.board_boats[x as usize][y as usize] = Some(i);
+        }
+        self.player.boats.push(boat);
+        Ok(())
+    }
+
+    // the valid positions of a boat of the class
+    fn boat_candidates(&self, class: Class) -> Vec<Boat> {
+        let mut candidates = vec![];
+        for x in 0..self.board_boats.len() {
+            for y in 0..self.board_boats[x].len() {
+                for direction in Direction::all(self.rules.diagonal_boats) {
+                    let boat = Boat::new(class, (x as u8, y as u8), direction);
+                    if self.check_boat(&boat).is_ok() {
+                        candidates.push(boat);
+                    }
+                }
+            }
+        }
+        candidates
+    }
+
+    // adds the boats of the fleet not placed yet at random valid positions,
+    // the last ones are placed again elsewhere when the next ones don't fit anymore
+    pub fn add_random_fleet(&mut self) -> Result<(), String> {
+        let missing: Vec<Class> = FLEET
+            .iter()
+            .filter(|c| self.player.boats.iter().all(|b| b.class != **c))
+            .cloned()
+            .collect();
+        // the number of placements tried before giving up, enough for any sensible map
+        let mut budget = 500;
+        if self.place_boats(&missing, &mut budget) {
+            Ok(())
+        } else {
+            Err("No room left for the fleet".to_string())
+        }
+    }
+
+    fn place_boats(&mut self, classes: &[Class], budget: &mut usize) -> bool {
+        let class = match classes.first() {
+            Some(class) => *class,
+            None => return true,
+        };
+        let mut candidates = self.boat_candidates(class);
+        candidates.shuffle(&mut rand::thread_rng());
+        for boat in candidates {
+            if *budget == 0 {
+                return false;
+            }
+            *budget -= 1;
+            if self.add_boat(boat).is_ok() {
+                if self.place_boats(&classes[1..], budget) {
+                    return true;
+                }
+                self.remove_last_boat();
+            }
+        }
+        false
+    }
+
+    fn remove_last_boat(&mut self) {
+        if let Some(boat) = self.player.boats.pop() {
+            for (x, y) in boat.cells() {
+                self.board_boats[x as usize][y as usize] = None;
+            }
+        }
+    }
+
+    // hides the mines in random empty water cells, with the obstacles rule
+    pub fn add_random_mines(&mut self) {
+        if !self.rules.obstacles {
+            return;
+        }
+        let mut candidates = vec![];
+        for x in 0..self.board_boats.len() {
+            for y in 0..self.board_boats[x].len() {
+                if self.board_boats[x][y] == None && self.terrain[x][y] == Terrain::Water {
+                    candidates.push((x, y));
+                }
+            }
+        }
+        for _ in 0..MINES.min(candidates.len()) {
+            let i = rand::thread_rng().gen_range(0, candidates.len());
+            let (x, y) = candidates.swap_remove(i);
+            self.terrain[x][y] = Terrain::Mine;
+        }
+    }
+
+    // a mine shot by the main player damages a random intact segment of their fleet
+    pub fn mine_damage(&mut self) -> Option<((u8, u8), Shot)> {
+        let mut segments = vec![];
+        for boat in &self.player.boats {
+            for (&(x, y), &alive) in boat.cells().iter().zip(&boat.detailed_life) {
+                if alive {
+                    segments.push((x as u8, y as u8));
+                }
+            }
+        }
+        if segments.is_empty() {
+            return None;
+        }
+        let p = segments[rand::thread_rng().gen_range(0, segments.len())];
+        Some((p, self.opponent_attack(p)))
+    }
+
+    // moves an undamaged boat, the other boats can't be crossed
+    pub fn move_boat(&mut self, class: Class, movement: Movement) -> Result<(), String> {
+        let i = match self.player.boats.iter().position(|b| b.class == class) {
+            Some(i) => i,
+            None => return Err(format!("No {} to move", class.name())),
+        };
+        let boat = &self.player.boats[i];
+        if boat.is_damaged() {
+            return Err(format!("The {} is damaged and can't move", class.name()));
+        }
+        let (dx, dy) = boat.direction.delta();
+        let (x, y) = (boat.position.0 as i8, boat.position.1 as i8);
+        let (x, y, direction) = match movement {
+            Movement::Forward => (x + dx, y + dy, boat.direction),
+            Movement::Backward => (x - dx, y - dy, boat.direction),
+            Movement::Rotate => (x, y, boat.direction.rotate()),
+        };
+        if !in_board!(x, y, self.board_boats.len(), self.board_boats[0].len()) {
+            return Err(format!("The {} can't leave the board", class.name()));
+        }
+
+        // the boat is removed from the board to be checked at its new position
+        // the same boat at another place, keeping its hull and the cooldown of its ability
+        let mut moved = boat.clone();
+        moved.position = (x as u8, y as u8);
+        moved.direction = direction;
+        self.set_cells(&self.player.boats[i].cells(), None);
+        let result = self.check_boat(&moved);
+        if result.is_ok() {
+            self.player.boats[i] = moved;
+        }
+        self.set_cells(&self.player.boats[i].cells(), Some(i as u8));
+        result
+    }
+
+    fn set_cells(&mut self, cells: &[(i8, i8)], value: Option<u8>) {
+        for &(x, y) in cells {
+            self.board_boats[x as usize][y as usize] = value;
+        }
+    }
+
+    // the opponent moved a boat, which may now be hidden behind any miss
+    pub fn forget_misses(&mut self) {
+        for column in &mut self.shot_boats {
+            for cell in column.iter_mut() {
+                if *cell == Some(false) {
+                    *cell = None;
+                }
+            }
+        }
+        for column in &mut self.revealed {
+            for cell in column.iter_mut() {
+                *cell = false;
+            }
+        }
+    }
+
+    pub fn occupied(&self, p: (u8, u8)) -> bool {
+        self.board_boats[p.0 as usize][p.1 as usize] != None
+    }
+
+    // result of an ability revealing a position of the opponent
+    pub fn reveal(&mut self, p: (u8, u8), occupied: bool) {
+        if self.shot(p) {
+            return;
+        }
+        if occupied {
+            self.revealed[p.0 as usize][p.1 as usize] = true;
+        } else {
+            self.shot_boats[p.0 as usize][p.1 as usize] = Some(false);
+        }
+    }
+
+    // checks that the boat of this class can use its ability now
+    pub fn ability_ready(&self, class: Class) -> Result<(), String> {
+        match self.player.boats.iter().find(|b| b.class == class) {
+            None => Err(format!("No {}", class.name())),
+            Some(b) if b.is_dead() => Err(format!("The {} is sunk", class.name())),
+            Some(b) if b.cooldown == USED => Err(format!(
+                "The {} of the {} was already used",
+                class.ability().name(),
+                class.name()
+            )),
+            Some(b) if b.cooldown > 0 => Err(format!(
+                "The {} of the {} is ready in {} turns",
+                class.ability().name(),
+                class.name(),
+                b.cooldown
+            )),
+            Some(_) => Ok(()),
+        }
+    }
+
+    // the abilities get closer to be ready at each turn of their owner
+    pub fn tick_cooldowns(&mut self) {
+        for boat in &mut self.player.boats {
+            if boat.cooldown != USED && boat.cooldown > 0 {
+                boat.cooldown -= 1;
+            }
+        }
+    }
+
+    // the positions targeted by the ability of this class which haven't been shot yet
+    pub fn ability_area(&self, class: Class, p: (u8, u8)) -> Vec<(u8, u8)> {
+        class
+            .ability()
+            .area(p, (self.shot_boats.len(), self.shot_boats[0].len()))
+            .into_iter()
+            .filter(|p| self.can_target(*p))
+            .collect()
+    }
+
+    // a ready ability of a surviving boat, aimed at a random position
+    pub fn get_auto_ability(&self) -> Option<(Class, (u8, u8))> {
+        let pending = self.revealed.iter().flatten().any(|r| *r);
+        for boat in &self.player.boats {
+            if self.ability_ready(boat.class).is_err()
+                // no need to reveal more positions before shooting the known ones
+                || (!boat.class.ability().attacks() && pending)
+            {
+                continue;
+            }
+            let p = self.get_auto_position();
+            if !self.ability_area(boat.class, p).is_empty() {
+                return Some((boat.class, p));
+            }
+        }
+        None
+    }
+
+    // whether a boat can't be placed on this empty position because of the no touching rule
+    pub fn forbidden(&self, p: (u8, u8)) -> bool {
+        self.rules.no_touching
+            && self.board_boats[p.0 as usize][p.1 as usize] == None
+            && self
+                .neighbours(p.0 as i8, p.1 as i8)
+                .iter()
+                .any(|&(x, y)| self.board_boats[x][y] != None)
     }
 
     // to improve ?
@@ -107,12 +530,34 @@ impl Game {
                 rand::thread_rng().gen_range(0, nb),
                 rand::thread_rng().gen_range(0, nb),
             );
-            if self.shot_boats[p.0 as usize][p.1 as usize] == None {
-                println!("auto_attack on {:?}", p);
+            if self.can_target(p) {
+                println!("auto_attack on {}", format_position(p));
                 return p;
             }
         }
     }
+
+    // n different positions, there must be enough positions not shot yet
+    pub fn get_auto_positions(&self, n: usize) -> Vec<(u8, u8)> {
+        // the revealed positions first
+        let mut ps = Vec::with_capacity(n);
+        for x in 0..self.revealed.len() {
+            for y in 0..self.revealed[x].len() {
+                let p = (x as u8, y as u8);
+                if ps.len() < n && self.revealed[x][y] && self.can_target(p) {
+                    println!("auto_attack on revealed {}", format_position(p));
+                    ps.push(p);
+                }
+            }
+        }
+        while ps.len() < n {
+            let p = self.get_auto_position();
+            if !ps.contains(&p) {
+                ps.push(p);
+            }
+        }
+        ps
+    }
 }
 
 #[derive(Debug)]
@@ -121,95 +566,521 @@ pub enum GameType {
         game: Game,                  // the game of the main player
         player: bool,                // true for player 1, false otherwise
         socket: std::net::TcpStream, // the socket to the other player
+        buffer: Vec<u8>,             // the start of a message not completely received
+        replay: Replay,
+        shadow: Game, // the board of the main player as the opponent sees it
     },
     Ai {
         game: Game, // the game of the main player
         player: bool,
         opponent: Game, // the game of the AI
+        replay: Replay,
+    },
+    // two players sharing the same device, player is true for the first one
+    Local {
+        game: Game,     // the game of the first player
+        player: bool,   // the player whose turn it is
+        opponent: Game, // the game of the second player
+        replay: Replay,
+    },
+    Party {
+        game: Game, // the game of the main player, the shots are in the party
+        party: Party,
+        replay: Replay, // only the fleet of the main player is recorded
     },
 }
 
 impl GameType {
-    // called when the opponent attacks a position
-    pub fn opponent_attack(&mut self, p: (u8, u8)) -> Result<(), String> {
-        println!("game_type : opponent_attack ({},{})", p.0, p.1);
+    pub fn replay(&self) -> &Replay {
         match self {
-            GameType::Network {
+            GameType::Network { replay, .. } => replay,
+            GameType::Ai { replay, .. } => replay,
+            GameType::Local { replay, .. } => replay,
+            GameType::Party { replay, .. } => replay,
+        }
+    }
+
+    pub fn rules(&self) -> Rules {
+        game!(self).rules
+    }
+
+    // the game of the player whose turn it is
+    fn attacker(&self) -> &Game {
+        match self {
+            GameType::Network { game, .. } | GameType::Party { game, .. } => game,
+            GameType::Ai {
                 game,
+                opponent,
+                player,
+                ..
+            }
+            | GameType::Local {
+                game,
+                opponent,
+                player,
+                ..
+            } => {
+                if *player {
+                    game
+                } else {
+                    opponent
+                }
+            }
+        }
+    }
+
+    fn attacker_mut(&mut self) -> &mut Game {
+        match self {
+            GameType::Network { game, .. } | GameType::Party { game, .. } => game,
+            GameType::Ai {
+                game,
+                opponent,
+                player,
+                ..
+            }
+            | GameType::Local {
+                game,
+                opponent,
+                player,
+                ..
+            } => {
+                if *player {
+                    game
+                } else {
+                    opponent
+                }
+            }
+        }
+    }
+
+    // whether the main player can attack now, always true when the players share the device
+    pub fn can_attack(&self) -> bool {
+        match self {
+            GameType::Network { player, .. } => *player,
+            GameType::Ai { player, .. } => *player,
+            GameType::Local { .. } => true,
+            GameType::Party { party, .. } => party.can_attack(),
+        }
+    }
+
+    // the game holding the shot board of the player whose turn it is
+    pub fn shot_board(&self) -> &Game {
+        match self {
+            GameType::Party { party, .. } => &party.boards[party.target as usize],
+            _ => self.attacker(),
+        }
+    }
+
+    // the game of the given player, true for the main one
+    pub fn player_game(&self, player: bool) -> &Game {
+        match self {
+            GameType::Ai { opponent, .. } | GameType::Local { opponent, .. } if !player => opponent,
+            _ => game!(self),
+        }
+    }
+
+    // number of positions to attack this turn
+    pub fn shots(&self) -> usize {
+        let game = self.attacker();
+        let board = self.shot_board();
+        let unknown = (0..board.shot_boats.len())
+            .flat_map(|x| (0..board.shot_boats[x].len()).map(move |y| (x as u8, y as u8)))
+            .filter(|p| board.can_target(*p))
+            .count();
+        game.rules.shots(game).min(unknown)
+    }
+
+    // called when the main player moves a boat instead of attacking
+    pub fn move_boat(&mut self, class: Class, movement: Movement) -> Result<(), String> {
+        if !self.rules().moving_boats || !self.can_attack() {
+            return Ok(());
+        }
+        if let Err(e) = self.attacker_mut().move_boat(class, movement) {
+            println!("{}", e);
+            return Ok(());
+        }
+        self.attacker_mut().tick_cooldowns();
+        println!("move {}", format_movement(class, movement));
+        match self {
+            GameType::Network {
                 socket,
                 player,
+                replay,
+                shadow,
+                ..
             } => {
-                let b = game.opponent_attack(p);
-                *player = true;
-                println!("Message sent : confirm ({}, {}) as {}", p.0, p.1, b);
+                replay.shift(true, class, movement);
+                // the opponent forgets their misses too
+                shadow.forget_misses();
+                *player = false;
+                println!("message sent : move {}", format_movement(class, movement));
                 result_map!(
-                    socket.write(&[CONFIRM, p.0, p.1, b as u8]),
+                    socket.write_all(&[MOVE, class as u8, movement as u8]),
                     |_| (),
                     |x: std::io::Error| x.to_string()
                 )
             }
-            GameType::Ai { game, .. } => {
-                game.opponent_attack(p);
-                Ok(())
+            GameType::Ai {
+                game,
+                player,
+                opponent,
+                replay,
             }
+            | GameType::Local {
+                game,
+                player,
+                opponent,
+                replay,
+            } => {
+                replay.shift(*player, class, movement);
+                if *player {
+                    opponent.forget_misses();
+                } else {
+                    game.forget_misses();
+                }
+                *player = !*player;
+                self.next_turn()
+            }
+            // moving boats is disabled in a party
+            GameType::Party { .. } => Ok(()),
         }
     }
 
-    // called to confirm the main player attack result
-    pub fn confirm_attack(&mut self, p: (u8, u8), b: bool) -> Result<(), String> {
-        println!("confirm_attack");
+    // lets the AI play if it is its turn
+    fn next_turn(&mut self) -> Result<(), String> {
+        if let GameType::Ai { player: false, .. } = self {
+            if self.is_over() == None {
+                return self.auto_attack();
+            }
+        }
+        Ok(())
+    }
+
+    // called with the damages of the mines shot by the opponent
+    pub fn opponent_mine(&mut self, damages: &[((u8, u8), Shot)]) {
+        if let GameType::Network { game, replay, .. } = self {
+            for &(q, shot) in damages {
+                game.confirm_attack(q, shot);
+                replay.mine(true, q, shot);
+            }
+        }
+    }
+
+    // called when the opponent moves one of their boats
+    pub fn opponent_move(&mut self, class: Class, movement: Movement) {
+        if let GameType::Network {
+            game,
+            player,
+            replay,
+            shadow,
+            ..
+        } = self
+        {
+            shadow.tick_cooldowns();
+            game.forget_misses();
+            replay.shift(false, class, movement);
+            *player = true;
+        }
+    }
+
+    // called when the player whose turn it is uses the ability of one of their boats
+    // instead of attacking, aimed at the given position
+    pub fn use_ability(&mut self, class: Class, p: (u8, u8)) -> Result<(), String> {
+        // abilities are disabled in a party
+        if let GameType::Network { player: false, .. } | GameType::Party { .. } = self {
+            return Ok(());
+        }
+        let attacker = self.attacker();
+        if !attacker.rules.abilities {
+            return Ok(());
+        }
+        if let Err(e) = attacker.ability_ready(class) {
+            println!("{}", e);
+            return Ok(());
+        }
+        let ability = class.ability();
+        let area = attacker.ability_area(class, p);
+        if area.is_empty() {
+            println!("Nothing left to target around {}", format_position(p));
+            return Ok(());
+        }
+        println!("ability {}", format_ability(ability, p));
+
+        let attacker = self.attacker_mut();
+        attacker.tick_cooldowns();
+        if let Some(boat) = attacker.player.boats.iter_mut().find(|b| b.class == class) {
+            boat.cooldown = ability.cooldown();
+        }
+
         match self {
-            GameType::Network { game, player, .. } => {
-                game.confirm_attack(p, b);
+            GameType::Network {
+                socket,
+                player,
+                replay,
+                ..
+            } => {
+                replay.ability(true, ability, p);
+                // the opponent finds the same area on their side
+                let message = [ABILITY, class as u8, p.0, p.1];
+                // no more attack until the opponent answers
                 *player = false;
+                println!("message sent : ability {}", format_ability(ability, p));
+                result_map!(socket.write_all(&message), |_| (), |x: std::io::Error| x
+                    .to_string())
+            }
+            GameType::Ai {
+                game,
+                opponent,
+                player,
+                replay,
+            }
+            | GameType::Local {
+                game,
+                opponent,
+                player,
+                replay,
+            } => {
+                replay.ability(*player, ability, p);
+                let target = if *player { opponent } else { game };
+                if ability.attacks() {
+                    let results: Vec<((u8, u8), Shot)> = area
+                        .iter()
+                        .map(|&q| (q, target.opponent_attack(q)))
+                        .collect();
+                    self.confirm_attack(&results)
+                } else {
+                    let reveals: Vec<((u8, u8), bool)> =
+                        area.iter().map(|&q| (q, target.occupied(q))).collect();
+                    self.confirm_reveal(&reveals)
+                }
+            }
+            GameType::Party { .. } => Ok(()),
+        }
+    }
+
+    // called when the opponent uses the ability of one of their boats aimed at the given position
+    pub fn opponent_ability(&mut self, class: Class, p: (u8, u8)) -> Result<(), String> {
+        let ability = class.ability();
+        println!(
+            "game_type : opponent_ability {}",
+            format_ability(ability, p)
+        );
+        if let GameType::Network {
+            game,
+            socket,
+            player,
+            replay,
+            shadow,
+            ..
+        } = self
+        {
+            // the area is found the same way as on the side of the opponent
+            if !shadow.rules.abilities {
+                println!("Unexpected message, the abilities are disabled");
+                return Ok(());
+            }
+            if let Err(e) = shadow.ability_ready(class) {
+                println!("Unexpected message, {}", e);
+                return Ok(());
+            }
+            let area = shadow.ability_area(class, p);
+            if area.is_empty() {
+                println!("Unexpected message, nothing left to target");
+                return Ok(());
+            }
+            shadow.tick_cooldowns();
+            if let Some(boat) = shadow.player.boats.iter_mut().find(|b| b.class == class) {
+                boat.cooldown = ability.cooldown();
+            }
+            replay.ability(false, ability, p);
+            if ability.attacks() {
+                return self.answer_attack(&area);
+            }
+            let mut message = vec![REVEAL, area.len() as u8];
+            for &q in &area {
+                let occupied = game.occupied(q);
+                shadow.reveal(q, occupied);
+                replay.reveal(false, q, occupied);
+                message.extend_from_slice(&[q.0, q.1, occupied as u8]);
+                println!("Message sent : reveal {}", format_reveal(q, occupied));
+            }
+            *player = true;
+            return result_map!(socket.write_all(&message), |_| (), |x: std::io::Error| x
+                .to_string());
+        }
+        Ok(())
+    }
+
+    // called with the results of the ability revealing positions of the player whose turn it is
+    pub fn confirm_reveal(&mut self, reveals: &[((u8, u8), bool)]) -> Result<(), String> {
+        println!("confirm_reveal");
+        match self {
+            GameType::Network { game, replay, .. } => {
+                for &(q, occupied) in reveals {
+                    game.reveal(q, occupied);
+                    replay.reveal(true, q, occupied);
+                }
                 Ok(())
             }
             GameType::Ai {
                 game,
                 player,
                 opponent,
+                replay,
+            }
+            | GameType::Local {
+                game,
+                player,
+                opponent,
+                replay,
             } => {
-                if *player {
-                    game.confirm_attack(p, b);
-                    *player = false;
-                    if self.is_over() == None {
-                        self.auto_attack()
+                for &(q, occupied) in reveals {
+                    replay.reveal(*player, q, occupied);
+                    if *player {
+                        game.reveal(q, occupied);
                     } else {
-                        Ok(())
+                        opponent.reveal(q, occupied);
                     }
-                } else {
-                    opponent.confirm_attack(p, b);
-                    *player = true;
-                    Ok(())
                 }
+                *player = !*player;
+                self.next_turn()
             }
+            GameType::Party { .. } => Err("No reveal in a party game".to_string()),
         }
     }
 
-    pub fn auto_attack(&mut self) -> Result<(), String> {
-        println!("auto_play");
-        if self.is_over() != None {
-            return Err("Game already over".to_string());
+    // called when the opponent attacks some positions
+    pub fn opponent_attack(&mut self, ps: &[(u8, u8)]) -> Result<(), String> {
+        println!("game_type : opponent_attack {}", format_positions(ps));
+        if let GameType::Network { shadow, .. } = self {
+            shadow.tick_cooldowns();
         }
-        let game = match self {
-            GameType::Network { player: true, .. } => {
-                return Err("Not your turn, invalid action".to_string())
+        self.answer_attack(ps)
+    }
+
+    // the results of an attack or an ability of the opponent
+    fn answer_attack(&mut self, ps: &[(u8, u8)]) -> Result<(), String> {
+        match self {
+            GameType::Network {
+                game,
+                socket,
+                player,
+                replay,
+                shadow,
+                ..
+            } => {
+                let mut message = vec![CONFIRM, ps.len() as u8];
+                let mut results = Vec::with_capacity(ps.len());
+                for &p in ps {
+                    let shot = game.opponent_attack(p);
+                    shadow.confirm_attack(p, shot);
+                    replay.attack(false, p, shot);
+                    message.extend_from_slice(&[p.0, p.1, shot as u8]);
+                    results.push((p, shot));
+                    println!("Message sent : confirm {}", format_attack(p, shot));
+                }
+                *player = !game.rules.keeps_turn(&results);
+                result_map!(socket.write_all(&message), |_| (), |x: std::io::Error| x
+                    .to_string())
+            }
+            GameType::Ai { game, .. } | GameType::Local { game, .. } => {
+                for &p in ps {
+                    game.opponent_attack(p);
+                }
+                Ok(())
+            }
+            // the attacks of a party are handled with check_party
+            GameType::Party { .. } => Err("Unexpected attack in a party game".to_string()),
+        }
+    }
+
+    // called to confirm the results of the attack of the player whose turn it is
+    pub fn confirm_attack(&mut self, results: &[((u8, u8), Shot)]) -> Result<(), String> {
+        println!("confirm_attack");
+        match self {
+            GameType::Network {
+                game,
+                player,
+                socket,
+                replay,
+                ..
+            } => {
+                let mut damages = vec![];
+                for &(p, shot) in results {
+                    game.confirm_attack(p, shot);
+                    replay.attack(true, p, shot);
+                    if shot == Shot::Mine {
+                        damages.extend(game.mine_damage());
+                    }
+                }
+                *player = game.rules.keeps_turn(results);
+                if damages.is_empty() {
+                    return Ok(());
+                }
+                // the opponent sees the damages of the mines
+                let mut message = vec![MINE, damages.len() as u8];
+                for &(q, shot) in &damages {
+                    replay.mine(false, q, shot);
+                    message.extend_from_slice(&[q.0, q.1, shot as u8]);
+                    println!("Message sent : mine {}", format_attack(q, shot));
+                }
+                result_map!(socket.write_all(&message), |_| (), |x: std::io::Error| x
+                    .to_string())
             }
-            GameType::Network { game, .. } => game,
             GameType::Ai {
                 game,
+                player,
                 opponent,
+                replay,
+            }
+            | GameType::Local {
+                game,
                 player,
+                opponent,
+                replay,
             } => {
-                if *player {
-                    game
-                } else {
-                    opponent
+                for &(p, shot) in results {
+                    replay.attack(*player, p, shot);
+                    let (attacker, target) = if *player {
+                        (&mut *game, &mut *opponent)
+                    } else {
+                        (&mut *opponent, &mut *game)
+                    };
+                    attacker.confirm_attack(p, shot);
+                    if shot == Shot::Mine {
+                        // the opponent sees the damage of the mine
+                        if let Some((q, damage)) = attacker.mine_damage() {
+                            replay.mine(!*player, q, damage);
+                            target.confirm_attack(q, damage);
+                        }
+                    }
+                }
+                // the turn passes to the other player unless the attacker plays again
+                if !game.rules.keeps_turn(results) {
+                    *player = !*player;
                 }
+                self.next_turn()
             }
-        };
-        let p = game.get_auto_position();
-        self.attack(p)
+            GameType::Party { .. } => Err("Unexpected confirmation in a party game".to_string()),
+        }
+    }
+
+    pub fn auto_attack(&mut self) -> Result<(), String> {
+        println!("auto_play");
+        if self.is_over() != None {
+            return Err("Game already over".to_string());
+        }
+        if let GameType::Network { player: true, .. } = self {
+            return Err("Not your turn, invalid action".to_string());
+        }
+        let attacker = self.attacker();
+        if attacker.rules.abilities {
+            if let Some((class, p)) = attacker.get_auto_ability() {
+                return self.use_ability(class, p);
+            }
+        }
+        let ps = attacker.get_auto_positions(self.shots());
+        self.attack(&ps)
     }
 
     pub fn is_over(&self) -> Option<bool> {
@@ -223,7 +1094,7 @@ impl GameType {
                     None
                 }
             }
-            GameType::Ai { game, opponent, .. } => {
+            GameType::Ai { game, opponent, .. } | GameType::Local { game, opponent, .. } => {
                 if game.player.is_dead() {
                     Some(false)
                 } else if opponent.player.is_dead() {
@@ -232,53 +1103,76 @@ impl GameType {
                     None
                 }
             }
+            GameType::Party { party, .. } => party.winner().map(|w| w == party.side(party.id)),
         }
     }
 
-    // called to attack a position
-    pub fn attack(&mut self, p: (u8, u8)) -> Result<(), String> {
-        println!("attack ({};{})", p.0, p.1);
+    // called to attack some positions, as many as given by shots
+    // invalid attacks are ignored
+    pub fn attack(&mut self, ps: &[(u8, u8)]) -> Result<(), String> {
+        println!("attack {}", format_positions(ps));
+        let board = self.shot_board();
+        for (i, p) in ps.iter().enumerate() {
+            if !board.can_target(*p) || ps[..i].contains(p) {
+                println!("{} can't be attacked", format_position(*p));
+                return Ok(());
+            }
+        }
+        if ps.len() != self.shots() {
+            println!("{} shots expected, got {}", self.shots(), ps.len());
+            return Ok(());
+        }
         match self {
-            GameType::Network {
-                game,
-                socket,
-                player,
-            } => {
-                /* TODO
-                    Record the attack position to avoid multiple attacks
-                    before the opponent answers
-                */
-                if *player && game.shot_boats[p.0 as usize][p.1 as usize] == None {
-                    println!("message sent : attack ({};{})", p.0, p.1);
-                    result_map!(
-                        socket.write(&[ATTACK, p.0, p.1]),
-                        |_| (),
-                        |x: std::io::Error| x.to_string()
-                    )
-                } else {
-                    Ok(())
+            GameType::Network { player: false, .. } => return Ok(()),
+            GameType::Party { party, .. } if !party.can_attack() => return Ok(()),
+            _ => (),
+        }
+        self.attacker_mut().tick_cooldowns();
+
+        match self {
+            GameType::Network { socket, player, .. } => {
+                let mut message = vec![ATTACK, ps.len() as u8];
+                for p in ps {
+                    message.extend_from_slice(&[p.0, p.1]);
                 }
+                // no more attack until the opponent answers
+                *player = false;
+                println!("message sent : attack {}", format_positions(ps));
+                result_map!(socket.write_all(&message), |_| (), |x: std::io::Error| x
+                    .to_string())
             }
             GameType::Ai {
                 game,
                 opponent,
                 player,
+                ..
+            }
+            | GameType::Local {
+                game,
+                opponent,
+                player,
+                ..
             } => {
-                let b = if *player {
-                    if !game.shot(p) {
-                        opponent
-                    } else {
-                        return Ok(());
-                    }
-                } else {
-                    if !opponent.shot(p) {
-                        game
-                    } else {
-                        return Ok(());
-                    }
+                let target = if *player { opponent } else { game };
+                let results: Vec<((u8, u8), Shot)> =
+                    ps.iter().map(|&p| (p, target.opponent_attack(p))).collect();
+                self.confirm_attack(&results)
+            }
+            GameType::Party { party, .. } => {
+                let mut message = vec![PARTY_ATTACK, party.id, party.target, ps.len() as u8];
+                for p in ps {
+                    message.extend_from_slice(&[p.0, p.1]);
                 }
-                .opponent_attack(p);
-                self.confirm_attack(p, b)
+                // no more attack until the target answers
+                party.waiting = true;
+                party.pending = Some((party.id, party.target));
+                println!(
+                    "message sent : attack {} on player {}",
+                    format_positions(ps),
+                    party.target + 1
+                );
+                party.send(&message, None);
+                Ok(())
             }
         }
     }
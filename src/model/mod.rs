@@ -1,4 +1,10 @@
+pub mod ability;
 pub mod boat;
 pub mod direction;
 pub mod game;
+pub mod notation;
+pub mod party;
 pub mod player;
+pub mod replay;
+pub mod rules;
+pub mod terrain;
@@ -0,0 +1,221 @@
+// text notation of the moves : columns are letters and rows are numbers starting at 1,
+// so (1, 6) is written "B7" and a placement is written "Carrier A1 right"
+use crate::model::{
+    ability::Ability,
+    boat::{Class, Movement},
+    direction::Direction,
+    game::Shot,
+};
+use crate::NB;
+
+type Position = (u8, u8);
+
+pub fn format_position(p: Position) -> String {
+    format!("{}{}", (b'A' + p.0) as char, p.1 as u32 + 1)
+}
+
+pub fn format_positions(ps: &[Position]) -> String {
+    ps.iter()
+        .map(|p| format_position(*p))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn parse_position(s: &str) -> Result<Position, String> {
+    let mut chars = s.chars();
+    let column = match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() => c.to_ascii_uppercase() as u8 - b'A',
+        _ => return Err(format!("Invalid position '{}' : missing column letter", s)),
+    };
+    let row = match chars.as_str().parse::<u8>() {
+        Ok(n) if n > 0 => n - 1,
+        _ => return Err(format!("Invalid position '{}' : missing row number", s)),
+    };
+    if column as i32 >= NB || row as i32 >= NB {
+        return Err(format!("Invalid position '{}' : out of the board", s));
+    }
+    Ok((column, row))
+}
+
+pub fn format_placement(class: Class, position: Position, direction: Direction) -> String {
+    format!(
+        "{} {} {}",
+        class.name(),
+        format_position(position),
+        direction.name().to_lowercase()
+    )
+}
+
+pub fn parse_placement(s: &str) -> Result<(Class, Position, Direction), String> {
+    match s.split_whitespace().collect::<Vec<_>>()[..] {
+        [class, position, direction] => Ok((
+            Class::from_name(class).ok_or_else(|| format!("Unknown boat class '{}'", class))?,
+            parse_position(position)?,
+            Direction::from_name(direction)
+                .ok_or_else(|| format!("Unknown direction '{}'", direction))?,
+        )),
+        _ => Err(format!("Invalid placement '{}'", s)),
+    }
+}
+
+pub fn format_attack(position: Position, shot: Shot) -> String {
+    format!(
+        "{} {}",
+        format_position(position),
+        match shot {
+            Shot::Miss => "miss",
+            Shot::Hit => "hit",
+            Shot::Sunk => "sunk",
+            Shot::Mine => "mine",
+        }
+    )
+}
+
+pub fn parse_attack(s: &str) -> Result<(Position, Shot), String> {
+    match s.split_whitespace().collect::<Vec<_>>()[..] {
+        [position, "miss"] => Ok((parse_position(position)?, Shot::Miss)),
+        [position, "hit"] => Ok((parse_position(position)?, Shot::Hit)),
+        [position, "sunk"] => Ok((parse_position(position)?, Shot::Sunk)),
+        [position, "mine"] => Ok((parse_position(position)?, Shot::Mine)),
+        _ => Err(format!("Invalid attack '{}'", s)),
+    }
+}
+
+// the damage of a mine on the fleet of the player who shot it, written "mine C4 hit"
+pub fn format_mine(position: Position, shot: Shot) -> String {
+    format!("mine {}", format_attack(position, shot))
+}
+
+pub fn parse_mine(s: &str) -> Result<(Position, Shot), String> {
+    match s.trim_start().splitn(2, ' ').collect::<Vec<_>>()[..] {
+        ["mine", attack] => parse_attack(attack),
+        _ => Err(format!("Invalid mine damage '{}'", s)),
+    }
+}
+
+pub fn format_movement(class: Class, movement: Movement) -> String {
+    format!("{} {}", class.name(), movement.name().to_lowercase())
+}
+
+pub fn parse_movement(s: &str) -> Result<(Class, Movement), String> {
+    match s.split_whitespace().collect::<Vec<_>>()[..] {
+        [class, movement] => Ok((
+            Class::from_name(class).ok_or_else(|| format!("Unknown boat class '{}'", class))?,
+            Movement::from_name(movement)
+                .ok_or_else(|| format!("Unknown movement '{}'", movement))?,
+        )),
+        _ => Err(format!("Invalid movement '{}'", s)),
+    }
+}
+
+pub fn format_ability(ability: Ability, position: Position) -> String {
+    format!("{} {}", ability.name(), format_position(position))
+}
+
+pub fn parse_ability(s: &str) -> Result<(Ability, Position), String> {
+    match s.split_whitespace().collect::<Vec<_>>()[..] {
+        [ability, position] => Ok((
+            Ability::from_name(ability).ok_or_else(|| format!("Unknown ability '{}'", ability))?,
+            parse_position(position)?,
+        )),
+        _ => Err(format!("Invalid ability '{}'", s)),
+    }
+}
+
+// the result of an ability revealing a position
+pub fn format_reveal(position: Position, occupied: bool) -> String {
+    format!(
+        "{} {}",
+        format_position(position),
+        if occupied { "occupied" } else { "empty" }
+    )
+}
+
+pub fn parse_reveal(s: &str) -> Result<(Position, bool), String> {
+    match s.split_whitespace().collect::<Vec<_>>()[..] {
+        [position, "occupied"] => Ok((parse_position(position)?, true)),
+        [position, "empty"] => Ok((parse_position(position)?, false)),
+        _ => Err(format!("Invalid reveal '{}'", s)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn position_round_trip() {
+        for x in 0..NB as u8 {
+            for y in 0..NB as u8 {
+                assert_eq!(parse_position(&format_position((x, y))), Ok((x, y)));
+            }
+        }
+        assert_eq!(format_position((1, 6)), "B7");
+        assert_eq!(parse_position("l12"), Ok((11, 11)));
+    }
+
+    #[test]
+    fn invalid_positions() {
+        assert!(parse_position("").is_err());
+        assert!(parse_position("7B").is_err());
+        assert!(parse_position("B0").is_err());
+        assert!(parse_position("M1").is_err());
+        assert!(parse_position("A13").is_err());
+    }
+
+    #[test]
+    fn placement_round_trip() {
+        let s = format_placement(Class::Carrier, (0, 0), Direction::Right);
+        assert_eq!(s, "Carrier A1 right");
+        let (class, position, direction) = parse_placement(&s).unwrap();
+        assert_eq!(format_placement(class, position, direction), s);
+        assert!(parse_placement("Canoe A1 right").is_err());
+        assert!(parse_placement("Carrier A1 sideways").is_err());
+        let s = format_placement(Class::Destroyer, (4, 2), Direction::DownLeft);
+        assert_eq!(s, "Destroyer E3 down-left");
+        assert_eq!(
+            parse_placement(&s),
+            Ok((Class::Destroyer, (4, 2), Direction::DownLeft))
+        );
+    }
+
+    #[test]
+    fn attack_round_trip() {
+        for shot in &[Shot::Miss, Shot::Hit, Shot::Sunk, Shot::Mine] {
+            assert_eq!(
+                parse_attack(&format_attack((3, 9), *shot)),
+                Ok(((3, 9), *shot))
+            );
+        }
+        assert_eq!(parse_attack("J4 miss"), Ok(((9, 3), Shot::Miss)));
+        assert!(parse_attack("J4 maybe").is_err());
+        assert_eq!(format_mine((2, 3), Shot::Sunk), "mine C4 sunk");
+        assert_eq!(parse_mine("mine C4 hit"), Ok(((2, 3), Shot::Hit)));
+        assert!(parse_mine("C4 hit").is_err());
+        assert!(parse_attack("mine C4 hit").is_err());
+    }
+
+    #[test]
+    fn movement_round_trip() {
+        let s = format_movement(Class::Cruiser, Movement::Backward);
+        assert_eq!(s, "Cruiser backward");
+        assert_eq!(parse_movement(&s), Ok((Class::Cruiser, Movement::Backward)));
+        assert!(parse_movement("Cruiser sideways").is_err());
+        assert!(parse_movement("B7 hit").is_err());
+    }
+
+    #[test]
+    fn ability_round_trip() {
+        let s = format_ability(Ability::Sonar, (4, 0));
+        assert_eq!(s, "Sonar E1");
+        assert_eq!(parse_ability(&s), Ok((Ability::Sonar, (4, 0))));
+        assert!(parse_ability("Carrier forward").is_err());
+        for &occupied in &[true, false] {
+            assert_eq!(
+                parse_reveal(&format_reveal((2, 3), occupied)),
+                Ok(((2, 3), occupied))
+            );
+        }
+        assert!(parse_reveal("C4 hit").is_err());
+    }
+}
@@ -0,0 +1,358 @@
+// free-for-all between 3 to 6 players over the network, or two teams of two players
+// the host is connected to every client and relays the messages between them
+use crate::model::{
+    direction::Direction,
+    game::{Game, Shot},
+    player::Player,
+    replay::Stats,
+    rules::Rules,
+    terrain::Map,
+};
+use crate::network::{PARTY_FLEET, PARTY_LEAVE};
+use std::io::Write;
+use std::net::TcpStream;
+
+pub const MIN_PLAYERS: usize = 3;
+pub const MAX_PLAYERS: usize = 6;
+pub const TEAM_PLAYERS: usize = 4;
+
+// a connection with the messages received but not read yet
+#[derive(Debug)]
+pub struct Peer {
+    pub socket: TcpStream,
+    pub buffer: Vec<u8>,
+    pub connected: bool, // false once a message can't be read or sent
+}
+
+#[derive(Debug)]
+pub struct Party {
+    pub id: u8,                    // the index of the main player, the host is 0
+    pub turn: u8,                  // the index of the player whose turn it is
+    pub target: u8,                // the player the main player fires at
+    pub waiting: bool,             // the main player waits for the results of their attack
+    pub pending: Option<(u8, u8)>, // the attacker and the target of an attack not confirmed yet
+    pub boards: Vec<Game>, // the known shots on the fleet of each player, by index, and the fleet of the teammate
+    pub alive: Vec<bool>,  // the eliminated players become spectators
+    pub peers: Vec<Peer>,  // one for each client for the host, only the host for a client
+    pub teams: bool,       // the players with an even index against the odd ones
+    pub map: Map,          // chosen by the host, the same for every player
+    pub stats: Stats,      // the results of the attacks of the main player
+    // the attacker, the attacked player, the position and the result of every shot
+    pub shots: Vec<(u8, u8, (u8, u8), Shot)>,
+    last: [u8; 2], // the last player of each team who played
+}
+
+impl Party {
+    pub fn new(
+        id: u8,
+        count: u8,
+        rules: Rules,
+        teams: bool,
+        map: &Map,
+        sockets: Vec<TcpStream>,
+    ) -> Result<Self, String> {
+        if teams && count as usize != TEAM_PLAYERS {
+            return Err(format!("Teams need {} players", TEAM_PLAYERS));
+        }
+        // no mine is laid nor triggered in a party, whatever the rules sent by the host
+        let rules = Rules {
+            obstacles: false,
+            ..rules
+        };
+        let mut boards = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            boards.push(Game::with_map(map, Player::new(vec![]), rules)?);
+        }
+        let mut party = Party {
+            id,
+            turn: 0,
+            target: 0,
+            waiting: false,
+            pending: None,
+            boards,
+            alive: vec![true; count as usize],
+            peers: sockets
+                .into_iter()
+                .map(|socket| Peer {
+                    socket,
+                    buffer: vec![],
+                    connected: true,
+                })
+                .collect(),
+            teams,
+            map: map.clone(),
+            stats: Stats::default(),
+            shots: vec![],
+            // so that the first player of each team plays first
+            last: [count - 2, count - 1],
+        };
+        party.target = party.targets()[0];
+        Ok(party)
+    }
+
+    pub fn is_host(&self) -> bool {
+        self.id == 0
+    }
+
+    // the team of the given player, or the player itself in a free-for-all
+    pub fn side(&self, player: u8) -> u8 {
+        if self.teams {
+            player % 2
+        } else {
+            player
+        }
+    }
+
+    pub fn teammate_of(&self, player: u8) -> Option<u8> {
+        if self.teams {
+            Some((player + 2) % TEAM_PLAYERS as u8)
+        } else {
+            None
+        }
+    }
+
+    pub fn teammate(&self) -> Option<u8> {
+        self.teammate_of(self.id)
+    }
+
+    pub fn can_attack(&self) -> bool {
+        self.turn == self.id && !self.waiting && self.alive[self.id as usize]
+    }
+
+    // the players who can be fired at by the main player
+    pub fn targets(&self) -> Vec<u8> {
+        self.targets_of(self.id)
+    }
+
+    pub fn targets_of(&self, player: u8) -> Vec<u8> {
+        (0..self.alive.len() as u8)
+            .filter(|&i| self.side(i) != self.side(player) && self.alive[i as usize])
+            .collect()
+    }
+
+    pub fn set_target(&mut self, target: u8) {
+        if self.targets().contains(&target) {
+            self.target = target;
+        }
+    }
+
+    // the winning side once a single one is left
+    pub fn winner(&self) -> Option<u8> {
+        let mut sides = (0..self.alive.len() as u8)
+            .filter(|&i| self.alive[i as usize])
+            .map(|i| self.side(i));
+        let first = sides.next()?;
+        if sides.all(|side| side == first) {
+            Some(first)
+        } else {
+            None
+        }
+    }
+
+    // applies the results of an attack, seen by every player
+    pub fn confirm(
+        &mut self,
+        from: u8,
+        attacker: u8,
+        dead: bool,
+        results: &[((u8, u8), Shot)],
+        rules: Rules,
+    ) {
+        self.pending = None;
+        let board = &mut self.boards[from as usize];
+        for &(p, shot) in results {
+            board.confirm_attack(p, shot);
+            self.shots.push((attacker, from, p, shot));
+            if attacker == self.id {
+                self.stats.add(shot);
+            }
+            // the fleet of the teammate is damaged too
+            if !board.player.boats.is_empty() {
+                board.opponent_attack(p);
+            }
+        }
+        if dead {
+            println!("Player {} is eliminated", from + 1);
+            self.alive[from as usize] = false;
+            if self.target == from {
+                self.target = self.targets().first().cloned().unwrap_or(from);
+            }
+        }
+        if attacker == self.id {
+            self.waiting = false;
+        }
+        // the turn passes to the next player still alive unless the attacker plays again
+        if !rules.keeps_turn(results) {
+            self.turn = self.next_player(attacker);
+        }
+    }
+
+    // a player who left the party is eliminated, an attack waiting for their answer is lost
+    pub fn leave(&mut self, player: u8) {
+        if !self.alive.get(player as usize).cloned().unwrap_or(false) {
+            return;
+        }
+        println!("Player {} left the party", player + 1);
+        self.alive[player as usize] = false;
+        if self.target == player {
+            self.target = self.targets().first().cloned().unwrap_or(player);
+        }
+        match self.pending {
+            Some((attacker, target)) if target == player => {
+                self.pending = None;
+                if attacker == self.id {
+                    self.waiting = false;
+                }
+                self.turn = self.next_player(attacker);
+            }
+            // the attack of the player who left is still confirmed by its target
+            Some(_) => (),
+            None if self.turn == player => self.turn = self.next_player(player),
+            None => (),
+        }
+    }
+
+    // the peer can't be reached anymore, the host tells the other players that its player left
+    pub fn lose_peer(&mut self, i: usize) {
+        if !self.peers[i].connected {
+            return;
+        }
+        self.peers[i].connected = false;
+        if self.is_host() {
+            let player = i as u8 + 1;
+            self.leave(player);
+            println!("message sent : player {} left", player + 1);
+            self.send(&[PARTY_LEAVE, player], None);
+        } else {
+            println!("The host left the party");
+        }
+    }
+
+    // a client can't play without the host
+    pub fn host_lost(&self) -> bool {
+        !self.is_host() && !self.peers[0].connected
+    }
+
+    fn next_player(&mut self, attacker: u8) -> u8 {
+        if self.teams {
+            // the teams play alternately, and the teammates in turn
+            let side = self.side(attacker) as usize;
+            self.last[side] = attacker;
+            let last = self.last[1 - side];
+            match self.teammate_of(last) {
+                Some(next) if self.alive[next as usize] => next,
+                _ => last,
+            }
+        } else {
+            let n = self.alive.len() as u8;
+            let mut next = (attacker + 1) % n;
+            while !self.alive[next as usize] && next != attacker {
+                next = (next + 1) % n;
+            }
+            next
+        }
+    }
+
+    // sends the fleet of the main player to their teammate
+    pub fn share_fleet(&mut self, player: &Player) -> Result<(), String> {
+        let teammate = match self.teammate() {
+            Some(teammate) => teammate,
+            None => return Ok(()),
+        };
+        let mut message = vec![PARTY_FLEET, self.id, player.boats.len() as u8];
+        for boat in &player.boats {
+            let direction = Direction::all(true)
+                .iter()
+                .position(|d| *d == boat.direction)
+                .unwrap_or(0);
+            message.extend_from_slice(&[
+                boat.class as u8,
+                boat.position.0,
+                boat.position.1,
+                direction as u8,
+            ]);
+        }
+        println!("message sent : fleet to player {}", teammate + 1);
+        if self.is_host() {
+            self.send_to(&message, teammate)
+        } else {
+            self.send(&message, None);
+            Ok(())
+        }
+    }
+
+    // sends a message to a single client, only for the host
+    pub fn send_to(&mut self, message: &[u8], player: u8) -> Result<(), String> {
+        let i = match (player as usize).checked_sub(1) {
+            Some(i) if i < self.peers.len() => i,
+            _ => return Err(format!("No player {}", player + 1)),
+        };
+        if self.peers[i].connected && self.peers[i].socket.write_all(message).is_err() {
+            self.lose_peer(i);
+        }
+        Ok(())
+    }
+
+    // sends a message to every connected peer but the given one, the unreachable ones are lost
+    pub fn send(&mut self, message: &[u8], except: Option<usize>) {
+        let mut lost = vec![];
+        for (i, peer) in self.peers.iter_mut().enumerate() {
+            if Some(i) != except && peer.connected && peer.socket.write_all(message).is_err() {
+                lost.push(i);
+            }
+        }
+        for i in lost {
+            self.lose_peer(i);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NB;
+
+    fn party(count: u8, teams: bool) -> Party {
+        let map = Map::new(NB as usize, NB as usize);
+        Party::new(0, count, Rules::default(), teams, &map, vec![]).unwrap()
+    }
+
+    #[test]
+    fn free_for_all_turns() {
+        let mut party = party(4, false);
+        assert_eq!(party.next_player(0), 1);
+        assert_eq!(party.next_player(3), 0);
+        party.alive[1] = false;
+        party.alive[2] = false;
+        assert_eq!(party.next_player(0), 3);
+        assert_eq!(party.winner(), None);
+        party.alive[3] = false;
+        assert_eq!(party.winner(), Some(0));
+    }
+
+    #[test]
+    fn team_turns() {
+        let mut party = party(TEAM_PLAYERS as u8, true);
+        // the teams play alternately, and the teammates in turn
+        let mut order = vec![0];
+        for _ in 0..7 {
+            let next = party.next_player(*order.last().unwrap());
+            order.push(next);
+        }
+        assert_eq!(order, vec![0, 1, 2, 3, 0, 1, 2, 3]);
+        // a team with a single player left still plays every other turn
+        party.alive[2] = false;
+        assert_eq!(party.next_player(1), 0);
+        assert_eq!(party.next_player(0), 3);
+        assert_eq!(party.next_player(3), 0);
+        assert_eq!(party.winner(), None);
+        party.alive[0] = false;
+        assert_eq!(party.winner(), Some(1));
+    }
+
+    #[test]
+    fn teams_need_four_players() {
+        let map = Map::new(NB as usize, NB as usize);
+        assert!(Party::new(0, 3, Rules::default(), true, &map, vec![]).is_err());
+    }
+}
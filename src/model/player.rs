@@ -1,4 +1,4 @@
-use crate::model::boat::Boat;
+use crate::model::boat::{Boat, FLEET};
 
 // to change ?
 // add a username ? any more informations ?
@@ -12,6 +12,21 @@ impl Player {
         Player { boats }
     }
 
+    // checks that the player has exactly one boat of each class of the fleet
+    pub fn check_fleet(&self) -> Result<(), String> {
+        for class in FLEET.iter() {
+            let n = self.boats.iter().filter(|b| b.class == *class).count();
+            if n != 1 {
+                return Err(format!(
+                    "The fleet must have exactly one {}, found {}",
+                    class.name(),
+                    n
+                ));
+            }
+        }
+        Ok(())
+    }
+
     pub fn is_dead(&self) -> bool {
         for boat in &self.boats {
             if !boat.is_dead() {
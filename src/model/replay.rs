@@ -0,0 +1,256 @@
+use crate::model::{
+    ability::Ability,
+    boat::{Class, Movement},
+    direction::Direction,
+    game::Shot,
+    notation::{
+        format_ability, format_attack, format_mine, format_movement, format_placement,
+        format_reveal,
+    },
+    player::Player,
+    rules::Rules,
+    terrain::Map,
+};
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+type Position = (u8, u8);
+
+// player is true for the main player, false for the opponent
+#[derive(Copy, Clone, Debug)]
+pub enum Move {
+    Place {
+        player: bool,
+        class: Class,
+        position: Position,
+        direction: Direction,
+    },
+    Attack {
+        player: bool,
+        position: Position,
+        shot: Shot,
+    },
+    // the damage of a mine of the player on the fleet of the other one, which isn't a shot
+    Mine {
+        player: bool,
+        position: Position,
+        shot: Shot,
+    },
+    Shift {
+        player: bool,
+        class: Class,
+        movement: Movement,
+    },
+    // followed by the attacks or the reveals of the targeted positions
+    Ability {
+        player: bool,
+        ability: Ability,
+        position: Position,
+    },
+    Reveal {
+        player: bool,
+        position: Position,
+        occupied: bool,
+    },
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (player, action) = match *self {
+            Move::Place {
+                player,
+                class,
+                position,
+                direction,
+            } => (player, format_placement(class, position, direction)),
+            Move::Attack {
+                player,
+                position,
+                shot,
+            } => (player, format_attack(position, shot)),
+            Move::Mine {
+                player,
+                position,
+                shot,
+            } => (player, format_mine(position, shot)),
+            Move::Shift {
+                player,
+                class,
+                movement,
+            } => (player, format_movement(class, movement)),
+            Move::Ability {
+                player,
+                ability,
+                position,
+            } => (player, format_ability(ability, position)),
+            Move::Reveal {
+                player,
+                position,
+                occupied,
+            } => (player, format_reveal(position, occupied)),
+        };
+        write!(
+            f,
+            "{} {}",
+            if player { "player" } else { "opponent" },
+            action
+        )
+    }
+}
+
+// the results of the attacks of a player
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub shots: usize,
+    pub hits: usize,
+    pub sunk: usize, // the boats sunk by the player
+}
+
+impl Stats {
+    pub fn add(&mut self, shot: Shot) {
+        self.shots += 1;
+        if shot.is_hit() {
+            self.hits += 1;
+        }
+        if shot == Shot::Sunk {
+            self.sunk += 1;
+        }
+    }
+
+    // the percentage of shots which hit a boat
+    pub fn accuracy(&self) -> usize {
+        if self.shots == 0 {
+            0
+        } else {
+            100 * self.hits / self.shots
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Record {
+    pub time: Duration, // since the beginning of the game
+    pub action: Move,
+}
+
+#[derive(Debug)]
+pub struct Replay {
+    start: Instant,
+    offset: Duration, // the duration of the recording before it was resumed
+    pub rules: Rules,
+    pub map: Map,
+    pub records: Vec<Record>,
+}
+
+impl Replay {
+    pub fn new(rules: Rules, map: Map) -> Self {
+        Replay {
+            start: Instant::now(),
+            offset: Duration::from_secs(0),
+            rules,
+            map,
+            records: vec![],
+        }
+    }
+
+    // used to resume a recording, the game is considered to restart where it stopped
+    pub fn from_records(rules: Rules, map: Map, records: Vec<Record>) -> Self {
+        Replay {
+            start: Instant::now(),
+            offset: records.last().map_or(Duration::from_secs(0), |r| r.time),
+            rules,
+            map,
+            records,
+        }
+    }
+
+    pub fn push(&mut self, action: Move) {
+        self.records.push(Record {
+            time: self.offset + self.start.elapsed(),
+            action,
+        });
+    }
+
+    pub fn stats(&self, player: bool) -> Stats {
+        let mut stats = Stats::default();
+        for record in &self.records {
+            if let Move::Attack {
+                player: p, shot, ..
+            } = record.action
+            {
+                if p == player {
+                    stats.add(shot);
+                }
+            }
+        }
+        stats
+    }
+
+    // the player, the position and the result of the last attack, the mines don't count
+    pub fn last_attack(&self) -> Option<(bool, Position, Shot)> {
+        self.records
+            .iter()
+            .rev()
+            .find_map(|record| match record.action {
+                Move::Attack {
+                    player,
+                    position,
+                    shot,
+                } => Some((player, position, shot)),
+                _ => None,
+            })
+    }
+
+    pub fn place_fleet(&mut self, player: bool, fleet: &Player) {
+        for boat in &fleet.boats {
+            self.push(Move::Place {
+                player,
+                class: boat.class,
+                position: boat.position,
+                direction: boat.direction,
+            });
+        }
+    }
+
+    pub fn attack(&mut self, player: bool, position: Position, shot: Shot) {
+        self.push(Move::Attack {
+            player,
+            position,
+            shot,
+        });
+    }
+
+    pub fn mine(&mut self, player: bool, position: Position, shot: Shot) {
+        self.push(Move::Mine {
+            player,
+            position,
+            shot,
+        });
+    }
+
+    pub fn shift(&mut self, player: bool, class: Class, movement: Movement) {
+        self.push(Move::Shift {
+            player,
+            class,
+            movement,
+        });
+    }
+
+    pub fn ability(&mut self, player: bool, ability: Ability, position: Position) {
+        self.push(Move::Ability {
+            player,
+            ability,
+            position,
+        });
+    }
+
+    pub fn reveal(&mut self, player: bool, position: Position, occupied: bool) {
+        self.push(Move::Reveal {
+            player,
+            position,
+            occupied,
+        });
+    }
+}
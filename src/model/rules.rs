@@ -0,0 +1,133 @@
+use crate::model::game::{Game, Shot};
+
+// the variants of the rules, chosen at the creation of the game
+// and shared with the opponent during the network handshake
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Rules {
+    pub salvo: bool,          // each turn, one shot for each surviving boat
+    pub shoot_again: bool,    // a player who hits plays again
+    pub no_touching: bool,    // boats can't touch each other, even diagonally
+    pub moving_boats: bool,   // a player may move an undamaged boat instead of attacking
+    pub abilities: bool,      // a player may use the ability of a boat instead of attacking
+    pub obstacles: bool,      // the boards have islands and hidden mines
+    pub diagonal_boats: bool, // boats may also be placed diagonally
+    pub shaped_hulls: bool,   // some boats have an L, T or square hull instead of a straight one
+}
+
+const SALVO: u8 = 1;
+const SHOOT_AGAIN: u8 = 1 << 1;
+const NO_TOUCHING: u8 = 1 << 2;
+const MOVING_BOATS: u8 = 1 << 3;
+const ABILITIES: u8 = 1 << 4;
+const OBSTACLES: u8 = 1 << 5;
+const DIAGONAL_BOATS: u8 = 1 << 6;
+const SHAPED_HULLS: u8 = 1 << 7;
+
+impl Rules {
+    // number of shots of the given player for a turn
+    pub fn shots(&self, game: &Game) -> usize {
+        if self.salvo {
+            game.player.boats.iter().filter(|b| !b.is_dead()).count()
+        } else {
+            1
+        }
+    }
+
+    // a party is played without moving boats, abilities nor obstacles
+    pub fn party_compatible(&self) -> bool {
+        !self.moving_boats && !self.abilities && !self.obstacles
+    }
+
+    // whether the player who got these results plays again
+    pub fn keeps_turn(&self, results: &[((u8, u8), Shot)]) -> bool {
+        self.shoot_again && results.iter().any(|r| r.1.is_hit())
+    }
+
+    pub fn to_byte(&self) -> u8 {
+        let mut b = 0;
+        if self.salvo {
+            b |= SALVO;
+        }
+        if self.shoot_again {
+            b |= SHOOT_AGAIN;
+        }
+        if self.no_touching {
+            b |= NO_TOUCHING;
+        }
+        if self.moving_boats {
+            b |= MOVING_BOATS;
+        }
+        if self.abilities {
+            b |= ABILITIES;
+        }
+        if self.obstacles {
+            b |= OBSTACLES;
+        }
+        if self.diagonal_boats {
+            b |= DIAGONAL_BOATS;
+        }
+        if self.shaped_hulls {
+            b |= SHAPED_HULLS;
+        }
+        b
+    }
+
+    // every bit is a rule, a new rule will need a second byte
+    pub fn from_byte(b: u8) -> Result<Self, String> {
+        Ok(Rules {
+            salvo: b & SALVO != 0,
+            shoot_again: b & SHOOT_AGAIN != 0,
+            no_touching: b & NO_TOUCHING != 0,
+            moving_boats: b & MOVING_BOATS != 0,
+            abilities: b & ABILITIES != 0,
+            obstacles: b & OBSTACLES != 0,
+            diagonal_boats: b & DIAGONAL_BOATS != 0,
+            shaped_hulls: b & SHAPED_HULLS != 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn byte_round_trip() {
+        for b in 0..=255 {
+            assert_eq!(Rules::from_byte(b).map(|r| r.to_byte()), Ok(b));
+        }
+        let all = Rules {
+            salvo: true,
+            shoot_again: true,
+            no_touching: true,
+            moving_boats: true,
+            abilities: true,
+            obstacles: true,
+            diagonal_boats: true,
+            shaped_hulls: true,
+        };
+        assert_eq!(all.to_byte(), 255);
+        assert_eq!(Rules::from_byte(255), Ok(all));
+        assert_eq!(Rules::default().to_byte(), 0);
+    }
+
+    #[test]
+    fn one_bit_by_rule() {
+        let shaped_hulls = Rules {
+            shaped_hulls: true,
+            ..Rules::default()
+        };
+        assert_eq!(Rules::from_byte(1 << 7), Ok(shaped_hulls));
+        let salvo = Rules {
+            salvo: true,
+            ..Rules::default()
+        };
+        assert_eq!(Rules::from_byte(1), Ok(salvo));
+        // each bit sets a different rule
+        let rules: Vec<Rules> = (0..8).map(|i| Rules::from_byte(1 << i).unwrap()).collect();
+        for (i, a) in rules.iter().enumerate() {
+            assert_ne!(*a, Rules::default());
+            assert!(rules[i + 1..].iter().all(|b| b != a));
+        }
+    }
+}
@@ -0,0 +1,157 @@
+// the static terrain of the cells of a board, and the maps giving the shape of the boards
+use crate::NB;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Terrain {
+    Water,
+    Island,  // no boat can be placed there, known by both players
+    Mine,    // hidden, damages the fleet of the player who shoots it, then disappears
+    Outside, // not part of the board
+}
+
+impl Terrain {
+    // the character used in the map files and the save files
+    pub fn to_char(&self) -> char {
+        match self {
+            Terrain::Water => '.',
+            Terrain::Island => '#',
+            Terrain::Mine => '*',
+            Terrain::Outside => '-',
+        }
+    }
+
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            '.' => Some(Terrain::Water),
+            '#' => Some(Terrain::Island),
+            '*' => Some(Terrain::Mine),
+            '-' => Some(Terrain::Outside),
+            _ => None,
+        }
+    }
+}
+
+// number of mines hidden by each player in their board
+pub const MINES: usize = 3;
+
+// the islands added to the map with the obstacles rule
+const ISLANDS: [(u8, u8); 9] = [
+    (2, 2),
+    (3, 2),
+    (2, 3),
+    (8, 3),
+    (5, 6),
+    (6, 6),
+    (3, 9),
+    (9, 8),
+    (9, 9),
+];
+
+// the shape of both boards of a game, with their own islands
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Map {
+    pub name: String,
+    pub terrain: Vec<Vec<Terrain>>, // by column, like the boards
+}
+
+impl Map {
+    // the full rectangle
+    pub fn new(width: usize, height: usize) -> Self {
+        Map {
+            name: "Classic".to_string(),
+            terrain: vec![vec![Terrain::Water; height]; width],
+        }
+    }
+
+    // a map of NB x NB cells from its rows, shorter rows are completed with outside cells
+    pub fn from_rows(name: &str, rows: &[&str]) -> Result<Self, String> {
+        if rows.len() > NB as usize {
+            return Err(format!("The map {} has more than {} rows", name, NB));
+        }
+        let mut map = Map {
+            name: name.to_string(),
+            terrain: vec![vec![Terrain::Outside; NB as usize]; NB as usize],
+        };
+        for (y, row) in rows.iter().enumerate() {
+            if row.chars().count() > NB as usize {
+                return Err(format!("The map {} has more than {} columns", name, NB));
+            }
+            for (x, c) in row.chars().enumerate() {
+                map.terrain[x][y] = match Terrain::from_char(c) {
+                    Some(Terrain::Mine) | None => {
+                        return Err(format!("Invalid cell '{}' in the map {}", c, name))
+                    }
+                    Some(terrain) => terrain,
+                };
+            }
+        }
+        Ok(map)
+    }
+
+    pub fn rows(&self) -> Vec<String> {
+        (0..self.terrain[0].len())
+            .map(|y| {
+                self.terrain
+                    .iter()
+                    .map(|column| column[y].to_char())
+                    .collect()
+            })
+            .collect()
+    }
+
+    // the terrain of a board before the mines are hidden
+    pub fn board(&self, obstacles: bool) -> Vec<Vec<Terrain>> {
+        let mut terrain = self.terrain.clone();
+        if obstacles {
+            for &(x, y) in ISLANDS.iter() {
+                if let Some(cell) = terrain
+                    .get_mut(x as usize)
+                    .and_then(|column| column.get_mut(y as usize))
+                {
+                    if *cell == Terrain::Water {
+                        *cell = Terrain::Island;
+                    }
+                }
+            }
+        }
+        terrain
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_round_trip() {
+        let rows = ["..#", "-..", ".", ""];
+        let map = Map::from_rows("small", &rows).unwrap();
+        assert_eq!(map.name, "small");
+        assert_eq!(map.terrain.len(), NB as usize);
+        assert_eq!(map.terrain[2][0], Terrain::Island);
+        assert_eq!(map.terrain[0][1], Terrain::Outside);
+        // the rows are completed with outside cells, up to NB rows
+        assert_eq!(map.terrain[1][2], Terrain::Outside);
+        assert_eq!(map.terrain[0][NB as usize - 1], Terrain::Outside);
+        let written = map.rows();
+        assert_eq!(written.len(), NB as usize);
+        assert!(written[0].starts_with("..#-"));
+        let written: Vec<&str> = written.iter().map(String::as_str).collect();
+        assert_eq!(Map::from_rows("small", &written), Ok(map));
+        let full = Map::new(NB as usize, NB as usize);
+        let written = full.rows();
+        let written: Vec<&str> = written.iter().map(String::as_str).collect();
+        assert_eq!(Map::from_rows("Classic", &written), Ok(full));
+    }
+
+    #[test]
+    fn invalid_rows() {
+        let long = ".".repeat(NB as usize + 1);
+        assert!(Map::from_rows("wide", &[&long]).is_err());
+        let rows = vec!["."; NB as usize + 1];
+        assert!(Map::from_rows("tall", &rows).is_err());
+        // the mines are hidden by the players, not drawn in the maps
+        assert!(Map::from_rows("mined", &["..*"]).is_err());
+        assert!(Map::from_rows("unknown", &["..x"]).is_err());
+    }
+}
@@ -1,54 +1,557 @@
-use crate::model::game::GameType;
-use std::io::Read;
+use crate::model::{
+    boat::{Boat, FLEET, MOVEMENTS},
+    direction::Direction,
+    game::{GameType, Shot},
+    notation::{format_ability, format_attack, format_movement, format_positions, format_reveal},
+    party::{Party, MAX_PLAYERS, MIN_PLAYERS},
+    rules::Rules,
+    terrain::Map,
+};
+use crate::NB;
+use std::io::{ErrorKind, Read, Write};
 use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
 
+// messages are the type followed by the number of positions,
+// then each position for an attack, or each position and its result for a confirmation,
+// a reveal or the damages of the mines shot by the sender
 pub const ATTACK: u8 = 42;
 pub const CONFIRM: u8 = 43;
+pub const REVEAL: u8 = 47;
+pub const MINE: u8 = 52;
+// a move is the type, the class of the boat and the movement, but not its position
+pub const MOVE: u8 = 45;
+// an ability is the type, the class of the boat and the aimed position,
+// the receiver finds the targeted positions itself
+pub const ABILITY: u8 = 46;
+// the handshake is the type, the protocol version and the rules
+pub const RULES: u8 = 44;
+// then the host sends the map, the type, the length of its name and its name,
+// followed by the terrain of each cell, row by row
+pub const MAP: u8 = 53;
+// in a party, an attack is the type, the attacker, the target and the number of positions,
+// followed by each position
+pub const PARTY_ATTACK: u8 = 48;
+// the confirmation is the type, the attacked player, the attacker, whether the attacked fleet
+// is sunk and the number of positions, followed by each position and its result
+pub const PARTY_CONFIRM: u8 = 49;
+// the start of a party is the type, the protocol version, the rules, the number of players,
+// the index of the receiver and whether the players are in teams, followed by the map
+pub const PARTY_START: u8 = 50;
+// a player who left the party is the type and the player, only sent by the host
+pub const PARTY_LEAVE: u8 = 54;
+// a fleet shared with a teammate is the type, the sender and the number of boats,
+// followed by the class, the position and the direction of each boat
+pub const PARTY_FLEET: u8 = 51;
+const PROTOCOL_VERSION: u8 = 12;
+const PORT: u16 = 8080;
+const PARTY_PORT: u16 = 8081;
 
 impl GameType {
     pub fn check_network(&mut self) -> Result<bool, String> {
-        let mut socket = match self {
-            GameType::Network { socket, .. } => socket.try_clone().map_err(|x| x.to_string())?,
+        let messages = match self {
+            GameType::Network { socket, buffer, .. } => {
+                receive(socket, buffer, network_message_len)?
+            }
             _ => panic!("Not a network game"),
         };
+        for message in messages {
+            self.network_message(&message)?;
+        }
+        Ok(false)
+    }
 
-        let size = 16;
-        let mut buffer: Vec<u8> = vec![0; size];
-        let n = match socket.read(&mut buffer) {
-            Ok(n) => {
-                if n < size {
-                    n
+    // a complete message of the opponent
+    fn network_message(&mut self, buffer: &[u8]) -> Result<(), String> {
+        let n = buffer.len();
+        if buffer[0] == MOVE && n == 3 {
+            match (
+                FLEET.get(buffer[1] as usize),
+                MOVEMENTS.get(buffer[2] as usize),
+            ) {
+                (Some(&class), Some(&movement)) => {
+                    println!(
+                        "Message received : move {}",
+                        format_movement(class, movement)
+                    );
+                    self.opponent_move(class, movement);
+                }
+                _ => println!("Unexpected message, invalid move"),
+            }
+            return Ok(());
+        }
+        if buffer[0] == ABILITY && n == 4 {
+            let p = (buffer[2], buffer[3]);
+            match FLEET.get(buffer[1] as usize) {
+                Some(&class) if in_board!(p.0, p.1, NB, NB) => {
+                    println!(
+                        "Message received : ability {}",
+                        format_ability(class.ability(), p)
+                    );
+                    self.opponent_ability(class, p)?;
+                }
+                _ => println!("Unexpected message, invalid ability"),
+            }
+            return Ok(());
+        }
+        let width = match buffer[0] {
+            ATTACK => 2,
+            CONFIRM | REVEAL | MINE => 3,
+            _ => 0,
+        };
+        if n >= 2 && width != 0 && n == 2 + width * buffer[1] as usize {
+            let mut results = vec![];
+            for m in buffer[2..n].chunks(width) {
+                if !in_board!(m[0], m[1], NB, NB) {
+                    println!("Unexpected message, position out of the board");
+                    return Ok(());
+                }
+                let shot = if buffer[0] == REVEAL {
+                    match m[2] {
+                        0 => Shot::Miss,
+                        1 => Shot::Hit,
+                        _ => {
+                            println!("Unexpected message, invalid reveal result");
+                            return Ok(());
+                        }
+                    }
+                } else if width == 3 {
+                    match Shot::from_byte(m[2]) {
+                        Some(shot) => shot,
+                        None => {
+                            println!("Unexpected message, invalid shot result");
+                            return Ok(());
+                        }
+                    }
                 } else {
-                    return Ok(false);
+                    Shot::Miss
+                };
+                results.push(((m[0], m[1]), shot));
+            }
+
+            if buffer[0] == ATTACK {
+                let ps: Vec<(u8, u8)> = results.iter().map(|r| r.0).collect();
+                println!("Message received : attack {}", format_positions(&ps));
+                self.opponent_attack(&ps)?;
+            } else if buffer[0] == MINE {
+                for &(p, shot) in &results {
+                    println!("Message received : mine {}", format_attack(p, shot));
+                }
+                self.opponent_mine(&results);
+            } else if buffer[0] == REVEAL {
+                let reveals: Vec<((u8, u8), bool)> =
+                    results.iter().map(|&(p, s)| (p, s.is_hit())).collect();
+                for &(p, occupied) in &reveals {
+                    println!("Message received : reveal {}", format_reveal(p, occupied));
+                }
+                self.confirm_reveal(&reveals)?;
+            } else {
+                for &(p, b) in &results {
+                    println!("Message received : confirm attack {}", format_attack(p, b));
+                }
+                self.confirm_attack(&results)?;
+            }
+        } else {
+            println!("Unexpected message, length {}", n);
+        }
+        Ok(())
+    }
+}
+
+impl GameType {
+    // reads the messages of a party, relayed by the host to the other players,
+    // returns true once the host is lost
+    pub fn check_party(&mut self) -> Result<bool, String> {
+        let mut messages = vec![];
+        match self {
+            GameType::Party { party, .. } => {
+                let mut lost = vec![];
+                for (i, peer) in party.peers.iter_mut().enumerate() {
+                    if !peer.connected {
+                        continue;
+                    }
+                    match receive(&mut peer.socket, &mut peer.buffer, party_message_len) {
+                        Ok(received) => messages.extend(received.into_iter().map(|m| (i, m))),
+                        Err(e) => {
+                            println!("{}", e);
+                            lost.push(i);
+                        }
+                    }
+                }
+                // the messages received before are still read
+                for i in lost {
+                    party.lose_peer(i);
+                }
+            }
+            _ => return Err("Not a party game".to_string()),
+        }
+        for (i, message) in messages {
+            if let GameType::Party { game, party, .. } = self {
+                // the host checks the messages of each client against the party before relaying
+                if party.is_host() {
+                    let player = i as u8 + 1;
+                    if let Err(e) = check_party_message(party, game.rules, &message, player) {
+                        println!("Unexpected message from player {}, {}", player + 1, e);
+                        continue;
+                    }
+                    if message[0] != PARTY_FLEET {
+                        party.send(&message, Some(i));
+                    } else if let Some(teammate) = party.teammate_of(player) {
+                        // a fleet is only seen by the teammate of its owner
+                        if teammate != party.id {
+                            party.send_to(&message, teammate)?;
+                        }
+                    }
+                }
+            }
+            self.party_message(&message)?;
+        }
+        match self {
+            GameType::Party { party, .. } => Ok(party.host_lost()),
+            _ => Ok(false),
+        }
+    }
+
+    fn party_message(&mut self, message: &[u8]) -> Result<(), String> {
+        let (game, party, replay) = match self {
+            GameType::Party {
+                game,
+                party,
+                replay,
+            } => (game, party, replay),
+            _ => return Err("Not a party game".to_string()),
+        };
+        let count = party.alive.len() as u8;
+        if message[0] == PARTY_LEAVE {
+            println!("Message received : player {} left", message[1] + 1);
+            party.leave(message[1]);
+            return Ok(());
+        }
+        if message[0] == PARTY_FLEET {
+            if Some(message[1]) != party.teammate() {
+                return Ok(());
+            }
+            let mut boats = vec![];
+            for m in message[3..].chunks(4) {
+                match (
+                    FLEET.get(m[0] as usize),
+                    Direction::all(true).get(m[3] as usize),
+                ) {
+                    (Some(&class), Some(&direction)) => {
+                        boats.push(Boat::new(class, (m[1], m[2]), direction))
+                    }
+                    _ => {
+                        println!("Unexpected message, invalid boat");
+                        return Ok(());
+                    }
                 }
             }
-            _ => return Ok(false),
+            println!("Message received : fleet of player {}", message[1] + 1);
+            let board = &mut party.boards[message[1] as usize];
+            for boat in boats {
+                if let Err(e) = board.add_boat(boat) {
+                    println!("Unexpected message, {}", e);
+                }
+            }
+            return Ok(());
+        }
+        if message[1] >= count || message[2] >= count {
+            println!("Unexpected message, unknown player");
+            return Ok(());
+        }
+        let (width, start) = if message[0] == PARTY_ATTACK {
+            (2, 4)
+        } else {
+            (3, 5)
         };
-        if n == 3 && buffer[0] == ATTACK {
-            let x = buffer[1];
-            let y = buffer[2];
-            println!("Message received : attack ({};{})", x, y);
-            self.opponent_attack((x, y))?;
-        } else if n == 4 && buffer[0] == CONFIRM {
-            let x = buffer[1];
-            let y = buffer[2];
-            let b = buffer[3] != 0;
+        let mut results = vec![];
+        for m in message[start..].chunks(width) {
+            if !in_board!(m[0], m[1], NB, NB) {
+                println!("Unexpected message, position out of the board");
+                return Ok(());
+            }
+            let shot = if width == 3 {
+                match Shot::from_byte(m[2]) {
+                    Some(shot) => shot,
+                    None => {
+                        println!("Unexpected message, invalid shot result");
+                        return Ok(());
+                    }
+                }
+            } else {
+                Shot::Miss
+            };
+            results.push(((m[0], m[1]), shot));
+        }
+
+        if message[0] == PARTY_ATTACK {
+            let (from, target) = (message[1], message[2]);
+            let ps: Vec<(u8, u8)> = results.iter().map(|r| r.0).collect();
             println!(
-                "Message received : confirm attack at ({};{}) as {}",
-                x, y, b
+                "Message received : attack {} from player {} on player {}",
+                format_positions(&ps),
+                from + 1,
+                target + 1
             );
-            self.confirm_attack((x, y), b)?;
-        } else if n == 0 {
-            return Err("Peer deconnected".to_string());
+            if from != party.turn || party.pending.is_some() {
+                println!("Unexpected message, attack out of turn");
+                return Ok(());
+            }
+            // the other players wait for the confirmation of the target
+            party.pending = Some((from, target));
+            if target != party.id {
+                return Ok(());
+            }
+            let mut confirm = vec![PARTY_CONFIRM, party.id, from, 0, ps.len() as u8];
+            let mut results = Vec::with_capacity(ps.len());
+            for &p in &ps {
+                let shot = game.opponent_attack(p);
+                replay.attack(false, p, shot);
+                confirm.extend_from_slice(&[p.0, p.1, shot as u8]);
+                results.push((p, shot));
+                println!("Message sent : confirm {}", format_attack(p, shot));
+            }
+            let dead = game.player.is_dead();
+            confirm[3] = dead as u8;
+            party.send(&confirm, None);
+            party.confirm(party.id, from, dead, &results, game.rules);
         } else {
-            println!("Unexpected message, length {}", buffer.len());
+            let (from, attacker, dead) = (message[1], message[2], message[3] != 0);
+            if party.pending != Some((attacker, from)) {
+                println!("Unexpected message, confirmation of no attack");
+                return Ok(());
+            }
+            for &(p, shot) in &results {
+                println!(
+                    "Message received : confirm attack {} on player {}",
+                    format_attack(p, shot),
+                    from + 1
+                );
+            }
+            party.confirm(from, attacker, dead, &results, game.rules);
         }
-        Ok(false)
+        Ok(())
     }
 }
 
+// whether the host can relay the message of a client, sent by the given player
+fn check_party_message(
+    party: &Party,
+    rules: Rules,
+    message: &[u8],
+    player: u8,
+) -> Result<(), String> {
+    match message[0] {
+        PARTY_ATTACK => {
+            let (attacker, target, n) = (message[1], message[2], message[3] as usize);
+            if attacker != player || party.turn != player || party.pending.is_some() {
+                return Err("attack out of turn".to_string());
+            }
+            if !party.targets_of(player).contains(&target) {
+                return Err(format!("invalid target {}", target as usize + 1));
+            }
+            // a salvo has a shot for each boat still afloat
+            let sunk = party
+                .shots
+                .iter()
+                .filter(|&&(_, attacked, _, shot)| attacked == player && shot == Shot::Sunk)
+                .count();
+            let shots = if rules.salvo {
+                FLEET.len().saturating_sub(sunk)
+            } else {
+                1
+            };
+            if n == 0 || n > shots {
+                return Err(format!("{} shots instead of {}", n, shots));
+            }
+        }
+        PARTY_CONFIRM => {
+            if message[1] != player || party.pending != Some((message[2], player)) {
+                return Err("confirmation of no attack".to_string());
+            }
+        }
+        PARTY_FLEET => {
+            if message[1] != player {
+                return Err("fleet of another player".to_string());
+            }
+        }
+        _ => return Err(format!("type {}", message[0])),
+    }
+    Ok(())
+}
+
+// the length of the party message at the start of the buffer, once it is complete
+fn party_message_len(buffer: &[u8]) -> Option<usize> {
+    let len = match buffer[0] {
+        PARTY_ATTACK => 4 + 2 * *buffer.get(3)? as usize,
+        PARTY_CONFIRM => 5 + 3 * *buffer.get(4)? as usize,
+        PARTY_FLEET => 3 + 4 * *buffer.get(2)? as usize,
+        PARTY_LEAVE => 2,
+        _ => 0, // unknown type
+    };
+    if buffer.len() >= len {
+        Some(len)
+    } else {
+        None
+    }
+}
+
+// the length of the message of a two-player game at the start of the buffer, once it is complete
+fn network_message_len(buffer: &[u8]) -> Option<usize> {
+    let len = match buffer[0] {
+        ATTACK => 2 + 2 * *buffer.get(1)? as usize,
+        CONFIRM | REVEAL | MINE => 2 + 3 * *buffer.get(1)? as usize,
+        MOVE => 3,
+        ABILITY => 4,
+        _ => 0, // unknown type
+    };
+    if buffer.len() >= len {
+        Some(len)
+    } else {
+        None
+    }
+}
+
+// the complete messages received from a socket, whose length is given by the type of message,
+// the rest is kept in the buffer for the next call
+fn receive(
+    socket: &mut TcpStream,
+    pending: &mut Vec<u8>,
+    message_len: fn(&[u8]) -> Option<usize>,
+) -> Result<Vec<Vec<u8>>, String> {
+    let mut buffer = [0; 256];
+    loop {
+        match socket.read(&mut buffer) {
+            Ok(0) => return Err("Peer deconnected".to_string()),
+            Ok(n) => pending.extend_from_slice(&buffer[..n]),
+            // the socket of a two-player game has a read timeout instead of being non blocking
+            Err(ref e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => {
+                break
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+    Ok(split_messages(pending, message_len))
+}
+
+// takes the complete messages at the start of the buffer,
+// the bytes of an unknown type are skipped one by one until a known type
+fn split_messages(pending: &mut Vec<u8>, message_len: fn(&[u8]) -> Option<usize>) -> Vec<Vec<u8>> {
+    let mut messages = vec![];
+    while !pending.is_empty() {
+        match message_len(pending) {
+            Some(0) => {
+                println!("Unexpected message, type {}", pending[0]);
+                pending.remove(0);
+            }
+            Some(len) => messages.push(pending.drain(..len).collect()),
+            None => break,
+        }
+    }
+    messages
+}
+
+// the host sends its rules and its map, and the client sends the rules back to agree on them
+pub fn handshake(
+    socket: &mut TcpStream,
+    host: bool,
+    rules: Rules,
+    map: &Map,
+) -> Result<(Rules, Map), String> {
+    socket
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .map_err(|x| x.to_string())?;
+    if host {
+        send_rules(socket, rules)?;
+        send_map(socket, map)?;
+    }
+    let received = receive_rules(socket)?;
+    let map = if host {
+        map.clone()
+    } else {
+        receive_map(socket)?
+    };
+    if host && received != rules {
+        return Err("The client didn't agree on the rules".to_string());
+    } else if !host {
+        send_rules(socket, received)?;
+    }
+    socket
+        .set_read_timeout(Some(Duration::from_nanos(1)))
+        .map_err(|x| x.to_string())?;
+    println!("Handshake done, rules : {:?}, map : {}", received, map.name);
+    Ok((received, map))
+}
+
+fn send_map(socket: &mut TcpStream, map: &Map) -> Result<(), String> {
+    // the length of the name is sent in a byte, so it is cut between two characters
+    let mut len = map.name.len().min(u8::max_value() as usize);
+    while !map.name.is_char_boundary(len) {
+        len -= 1;
+    }
+    let name = &map.name[..len];
+    let mut message = vec![MAP, len as u8];
+    message.extend(name.bytes());
+    for row in map.rows() {
+        message.extend(row.bytes());
+    }
+    socket.write_all(&message).map_err(|x| x.to_string())
+}
+
+fn receive_map(socket: &mut TcpStream) -> Result<Map, String> {
+    let mut header = [0; 2];
+    socket
+        .read_exact(&mut header)
+        .map_err(|x| format!("Handshake failed : {}", x))?;
+    if header[0] != MAP {
+        return Err("Handshake failed : unexpected message".to_string());
+    }
+    let mut buffer = vec![0; header[1] as usize + (NB * NB) as usize];
+    socket
+        .read_exact(&mut buffer)
+        .map_err(|x| format!("Handshake failed : {}", x))?;
+    let (name, cells) = buffer.split_at(header[1] as usize);
+    let rows: Vec<String> = cells
+        .chunks(NB as usize)
+        .map(|row| row.iter().map(|&c| c as char).collect())
+        .collect();
+    let rows: Vec<&str> = rows.iter().map(String::as_str).collect();
+    Map::from_rows(&String::from_utf8_lossy(name), &rows)
+        .map_err(|x| format!("Handshake failed : {}", x))
+}
+
+fn send_rules(socket: &mut TcpStream, rules: Rules) -> Result<(), String> {
+    socket
+        .write_all(&[RULES, PROTOCOL_VERSION, rules.to_byte()])
+        .map_err(|x| x.to_string())
+}
+
+fn receive_rules(socket: &mut TcpStream) -> Result<Rules, String> {
+    let mut buffer = [0; 3];
+    socket
+        .read_exact(&mut buffer)
+        .map_err(|x| format!("Handshake failed : {}", x))?;
+    if buffer[0] != RULES {
+        return Err("Handshake failed : unexpected message".to_string());
+    } else if buffer[1] != PROTOCOL_VERSION {
+        return Err(format!(
+            "Handshake failed : protocol version {} (expected {})",
+            buffer[1], PROTOCOL_VERSION
+        ));
+    }
+    Rules::from_byte(buffer[2])
+}
+
 pub fn create_host_socket() -> Result<TcpListener, String> {
-    let mut tcp_list = TcpListener::bind("0.0.0.0:8080").map_err(|x| x.to_string());
+    listen(PORT)
+}
+
+pub fn create_party_socket() -> Result<TcpListener, String> {
+    listen(PARTY_PORT)
+}
+
+fn listen(port: u16) -> Result<TcpListener, String> {
+    let mut tcp_list = TcpListener::bind(("0.0.0.0", port)).map_err(|x| x.to_string());
     if let Ok(listener) = &mut tcp_list {
         listener.set_nonblocking(true).map_err(|x| x.to_string())?;
     }
@@ -57,7 +560,13 @@ pub fn create_host_socket() -> Result<TcpListener, String> {
 
 // TODO : improve error handling
 pub fn find_host(address: &str) -> Result<TcpStream, String> {
-    TcpStream::connect((address, 8080)).map_err(|x| x.to_string())
+    TcpStream::connect((address, PORT)).map_err(|x| x.to_string())
+}
+
+pub fn find_party(address: &str) -> Result<TcpStream, String> {
+    let socket = TcpStream::connect((address, PARTY_PORT)).map_err(|x| x.to_string())?;
+    socket.set_nonblocking(true).map_err(|x| x.to_string())?;
+    Ok(socket)
 }
 
 // TODO : improve error handling
@@ -68,3 +577,140 @@ pub fn wait_client(listener: &TcpListener) -> Option<TcpStream> {
     }
     None
 }
+
+// the host tells each client the rules, the number of players, its index and the map
+pub fn start_party(
+    mut sockets: Vec<TcpStream>,
+    rules: Rules,
+    teams: bool,
+    map: &Map,
+) -> Result<Party, String> {
+    let count = sockets.len() as u8 + 1;
+    for (i, socket) in sockets.iter_mut().enumerate() {
+        socket.set_nonblocking(true).map_err(|x| x.to_string())?;
+        socket
+            .write_all(&[
+                PARTY_START,
+                PROTOCOL_VERSION,
+                rules.to_byte(),
+                count,
+                i as u8 + 1,
+                teams as u8,
+            ])
+            .map_err(|x| x.to_string())?;
+        send_map(socket, map)?;
+    }
+    println!(
+        "Party started with {} players, rules : {:?}, map : {}",
+        count, rules, map.name
+    );
+    Party::new(0, count, rules, teams, map, sockets)
+}
+
+// the party joined by a client, once the host has started it
+pub fn wait_party_start(socket: &TcpStream) -> Result<Option<Party>, String> {
+    let mut buffer = [0; 6];
+    match socket.peek(&mut buffer) {
+        Ok(0) => return Err("Host deconnected".to_string()),
+        Ok(n) if n == buffer.len() => (),
+        Ok(_) => return Ok(None),
+        Err(ref e) if e.kind() == ErrorKind::WouldBlock => return Ok(None),
+        Err(e) => return Err(e.to_string()),
+    }
+    let mut socket = socket.try_clone().map_err(|x| x.to_string())?;
+    socket.read_exact(&mut buffer).map_err(|x| x.to_string())?;
+    if buffer[0] != PARTY_START {
+        return Err("Unexpected message, the party didn't start".to_string());
+    } else if buffer[1] != PROTOCOL_VERSION {
+        return Err(format!(
+            "Protocol version {} (expected {})",
+            buffer[1], PROTOCOL_VERSION
+        ));
+    }
+    let (count, id) = (buffer[3] as usize, buffer[4] as usize);
+    if count < MIN_PLAYERS || count > MAX_PLAYERS || id == 0 || id >= count {
+        return Err(format!("Invalid party of {} players", count));
+    }
+    let rules = Rules::from_byte(buffer[2])?;
+    // the map is sent right after the start, it is waited for like during a handshake
+    socket.set_nonblocking(false).map_err(|x| x.to_string())?;
+    socket
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .map_err(|x| x.to_string())?;
+    let map = receive_map(&mut socket)?;
+    socket.set_nonblocking(true).map_err(|x| x.to_string())?;
+    println!(
+        "Party joined as player {}, rules : {:?}, map : {}",
+        buffer[4] + 1,
+        rules,
+        map.name
+    );
+    Party::new(
+        buffer[4],
+        buffer[3],
+        rules,
+        buffer[5] != 0,
+        &map,
+        vec![socket],
+    )
+    .map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_messages() {
+        assert_eq!(network_message_len(&[ATTACK]), None);
+        assert_eq!(network_message_len(&[ATTACK, 2, 0, 0, 1]), None);
+        assert_eq!(network_message_len(&[ATTACK, 2, 0, 0, 1, 1]), Some(6));
+        assert_eq!(network_message_len(&[CONFIRM, 1, 3, 4]), None);
+        assert_eq!(network_message_len(&[CONFIRM, 1, 3, 4, 0]), Some(5));
+        assert_eq!(network_message_len(&[MOVE, 1]), None);
+        assert_eq!(network_message_len(&[ABILITY, 0, 5, 5]), Some(4));
+        assert_eq!(network_message_len(&[0]), Some(0));
+    }
+
+    #[test]
+    fn several_messages() {
+        let mut pending = vec![MOVE, 1, 2, ATTACK, 1, 4, 5, REVEAL, 1, 6];
+        let messages = split_messages(&mut pending, network_message_len);
+        assert_eq!(messages, vec![vec![MOVE, 1, 2], vec![ATTACK, 1, 4, 5]]);
+        // the end of the reveal comes with the next read
+        assert_eq!(pending, vec![REVEAL, 1, 6]);
+        pending.extend_from_slice(&[7, 1]);
+        let messages = split_messages(&mut pending, network_message_len);
+        assert_eq!(messages, vec![vec![REVEAL, 1, 6, 7, 1]]);
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn party_messages() {
+        assert_eq!(party_message_len(&[PARTY_ATTACK, 0, 1]), None);
+        assert_eq!(party_message_len(&[PARTY_ATTACK, 0, 1, 1, 2]), None);
+        assert_eq!(party_message_len(&[PARTY_ATTACK, 0, 1, 1, 2, 3]), Some(6));
+        assert_eq!(party_message_len(&[PARTY_CONFIRM, 1, 0, 0, 1, 2, 3]), None);
+        assert_eq!(
+            party_message_len(&[PARTY_CONFIRM, 1, 0, 0, 1, 2, 3, 0]),
+            Some(8)
+        );
+        assert_eq!(party_message_len(&[PARTY_FLEET, 1, 1, 0, 0, 0]), None);
+        assert_eq!(party_message_len(&[PARTY_FLEET, 1, 1, 0, 0, 0, 1]), Some(7));
+        assert_eq!(party_message_len(&[PARTY_LEAVE]), None);
+        assert_eq!(party_message_len(&[PARTY_LEAVE, 2]), Some(2));
+        assert_eq!(party_message_len(&[ATTACK]), Some(0));
+        let mut pending = vec![PARTY_LEAVE, 2, 0, PARTY_ATTACK, 0, 1, 1, 2];
+        let messages = split_messages(&mut pending, party_message_len);
+        assert_eq!(messages, vec![vec![PARTY_LEAVE, 2]]);
+        assert_eq!(pending, vec![PARTY_ATTACK, 0, 1, 1, 2]);
+    }
+
+    #[test]
+    fn unknown_type() {
+        let mut pending = vec![0, 255, MOVE, 0, 1, 7];
+        let messages = split_messages(&mut pending, network_message_len);
+        assert_eq!(messages, vec![vec![MOVE, 0, 1]]);
+        assert!(pending.is_empty());
+    }
+}
@@ -0,0 +1,587 @@
+// save and load of in-progress games, replays and fleet layouts
+use crate::model::{
+    boat::Boat,
+    game::{Game, GameType},
+    notation::{
+        format_placement, format_position, parse_ability, parse_attack, parse_mine, parse_movement,
+        parse_placement, parse_position, parse_reveal,
+    },
+    player::Player,
+    replay::{Move, Record, Replay},
+    rules::Rules,
+    terrain::{Map, Terrain},
+};
+use crate::NB;
+use std::{
+    env, fs,
+    iter::Enumerate,
+    path::{Path, PathBuf},
+    str::Lines,
+    time::Duration,
+};
+
+pub const SAVE_FILE: &str = "battleship.save";
+pub const REPLAY_FILE: &str = "last_game.replay";
+pub const LAYOUT_DIR: &str = "layouts";
+pub const MAP_DIR: &str = "maps";
+// environment variable giving the directory of the layouts shared by a team
+pub const SHARED_LAYOUT_VAR: &str = "BATTLESHIP_SHARED_LAYOUTS";
+const LAYOUT_EXTENSION: &str = "layout";
+
+const HEADER: &str = "battleship-save";
+const REPLAY_HEADER: &str = "battleship-replay";
+// to increment each time the format changes
+const VERSION: u32 = 10;
+const REPLAY_VERSION: u32 = 5;
+// layouts have no checksum, to be written or shared by hand
+const LAYOUT_HEADER: &str = "battleship-layout";
+const LAYOUT_VERSION: u32 = 1;
+// maps have no checksum either, they are made by hand
+const MAP_HEADER: &str = "battleship-map";
+const MAP_VERSION: u32 = 1;
+const MAP_EXTENSION: &str = "map";
+
+impl GameType {
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let (mode, game, player, opponent, replay) = match self {
+            GameType::Ai {
+                game,
+                player,
+                opponent,
+                replay,
+            } => ("ai", game, player, opponent, replay),
+            GameType::Local {
+                game,
+                player,
+                opponent,
+                replay,
+            } => ("local", game, player, opponent, replay),
+            GameType::Network { .. } | GameType::Party { .. } => {
+                return Err("Network games can't be saved".to_string())
+            }
+        };
+
+        let mut body = format!(
+            "mode {}\nturn {}\nrules {}\n",
+            mode,
+            *player as u8,
+            game.rules.to_byte()
+        );
+        write_map(&mut body, &replay.map);
+        write_game(&mut body, "game", game);
+        write_game(&mut body, "opponent", opponent);
+        write_records(&mut body, &replay.records);
+
+        write_file(path, HEADER, VERSION, &body)?;
+        println!("Game saved in {}", path);
+        Ok(())
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = read_file(path)?;
+        let body = check_content(&content, HEADER, VERSION)?;
+
+        let mut reader = Reader {
+            lines: body.lines().enumerate(),
+            line: 1,
+        };
+        let local = match reader.expect("mode")?.as_slice() {
+            ["ai"] => false,
+            ["local"] => true,
+            _ => return Err(reader.error("invalid mode")),
+        };
+        let player = match reader.expect("turn")?.as_slice() {
+            ["0"] => false,
+            ["1"] => true,
+            _ => return Err(reader.error("invalid turn")),
+        };
+        let rules = read_rules(&mut reader)?;
+        let map = read_map(&mut reader)?;
+        let game = read_game(&mut reader, "game", &map, rules)?;
+        let opponent = read_game(&mut reader, "opponent", &map, rules)?;
+        let records = read_records(&mut reader)?;
+        reader.end()?;
+
+        println!("Game loaded from {}", path);
+        let replay = Replay::from_records(rules, map, records);
+        Ok(if local {
+            GameType::Local {
+                game,
+                player,
+                opponent,
+                replay,
+            }
+        } else {
+            GameType::Ai {
+                game,
+                player,
+                opponent,
+                replay,
+            }
+        })
+    }
+}
+
+impl Replay {
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let mut body = format!("rules {}\n", self.rules.to_byte());
+        write_map(&mut body, &self.map);
+        write_records(&mut body, &self.records);
+        write_file(path, REPLAY_HEADER, REPLAY_VERSION, &body)?;
+        println!("Replay saved in {}", path);
+        Ok(())
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = read_file(path)?;
+        let body = check_content(&content, REPLAY_HEADER, REPLAY_VERSION)?;
+
+        let mut reader = Reader {
+            lines: body.lines().enumerate(),
+            line: 1,
+        };
+        let rules = read_rules(&mut reader)?;
+        let map = read_map(&mut reader)?;
+        let records = read_records(&mut reader)?;
+        reader.end()?;
+
+        println!("Replay loaded from {}", path);
+        Ok(Replay::from_records(rules, map, records))
+    }
+}
+
+// saves a complete fleet as LAYOUT_DIR/name.layout
+pub fn save_layout(player: &Player, name: &str) -> Result<PathBuf, String> {
+    player.check_fleet()?;
+    let mut content = format!("{} {}\n", LAYOUT_HEADER, LAYOUT_VERSION);
+    for boat in &player.boats {
+        content.push_str(&format_placement(boat.class, boat.position, boat.direction));
+        content.push('\n');
+    }
+
+    fs::create_dir_all(LAYOUT_DIR).map_err(|x| format!("Can't create {} : {}", LAYOUT_DIR, x))?;
+    let path = Path::new(LAYOUT_DIR).join(format!("{}.{}", name, LAYOUT_EXTENSION));
+    fs::write(&path, content).map_err(|x| format!("Can't write {} : {}", path.display(), x))?;
+    println!("Layout saved in {}", path.display());
+    Ok(path)
+}
+
+// the first layout-<n> name not already used
+pub fn next_layout_name() -> String {
+    (1..)
+        .map(|n| format!("layout-{}", n))
+        .find(|name| {
+            !Path::new(LAYOUT_DIR)
+                .join(format!("{}.{}", name, LAYOUT_EXTENSION))
+                .exists()
+        })
+        .unwrap()
+}
+
+// loads a layout and checks it is a complete fleet that fits in the map
+pub fn load_layout(path: &Path, map: &Map, rules: Rules) -> Result<Game, String> {
+    let content = read_file(&path.to_string_lossy())?;
+    let mut lines = content.lines();
+    let header = format!("{} {}", LAYOUT_HEADER, LAYOUT_VERSION);
+    if lines.next().map(str::trim) != Some(header.as_str()) {
+        return Err(format!(
+            "{} is not a version {} layout",
+            path.display(),
+            LAYOUT_VERSION
+        ));
+    }
+
+    let mut boats = vec![];
+    for line in lines
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+    {
+        let (class, position, direction) =
+            parse_placement(line).map_err(|x| format!("{} : {}", path.display(), x))?;
+        boats.push(Boat::new(class, position, direction));
+    }
+    let player = Player::new(boats);
+    player
+        .check_fleet()
+        .and_then(|_| Game::with_map(map, player, rules))
+        .map_err(|x| format!("{} : {}", path.display(), x))
+}
+
+// loads a map, whether a fleet fits in it depends on the rules it's played with
+pub fn load_map(path: &Path) -> Result<Map, String> {
+    let content = read_file(&path.to_string_lossy())?;
+    let mut lines = content.lines();
+    let header = format!("{} {}", MAP_HEADER, MAP_VERSION);
+    if lines.next().map(str::trim) != Some(header.as_str()) {
+        return Err(format!(
+            "{} is not a version {} map",
+            path.display(),
+            MAP_VERSION
+        ));
+    }
+
+    let name = path
+        .file_stem()
+        .map_or("map".to_string(), |x| x.to_string_lossy().into_owned());
+    let rows: Vec<&str> = lines
+        .map(str::trim_end)
+        .filter(|l| !l.is_empty() && !l.starts_with(';'))
+        .collect();
+    Map::from_rows(&name, &rows).map_err(|x| format!("{} : {}", path.display(), x))
+}
+
+// the classic map followed by the valid maps of MAP_DIR
+pub fn list_maps() -> Vec<Map> {
+    let mut maps = vec![Map::new(NB as usize, NB as usize)];
+    if let Ok(entries) = fs::read_dir(MAP_DIR) {
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().map_or(false, |e| e == MAP_EXTENSION))
+            .collect();
+        paths.sort();
+        for path in paths {
+            match load_map(&path) {
+                Ok(map) => maps.push(map),
+                Err(e) => println!("{}", e),
+            }
+        }
+    }
+    maps
+}
+
+// the saved layouts, and whether they come from the shared directory
+pub fn list_layouts() -> Vec<(PathBuf, bool)> {
+    let mut dirs = vec![(PathBuf::from(LAYOUT_DIR), false)];
+    if let Some(shared) = env::var_os(SHARED_LAYOUT_VAR) {
+        dirs.push((PathBuf::from(shared), true));
+    }
+
+    let mut layouts = vec![];
+    for (dir, shared) in dirs {
+        if let Ok(entries) = fs::read_dir(&dir) {
+            let mut paths: Vec<PathBuf> = entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.extension().map_or(false, |e| e == LAYOUT_EXTENSION))
+                .collect();
+            paths.sort();
+            layouts.extend(paths.into_iter().map(|p| (p, shared)));
+        }
+    }
+    layouts
+}
+
+fn write_file(path: &str, header: &str, version: u32, body: &str) -> Result<(), String> {
+    let content = format!(
+        "{} {}\n{}checksum {:016x}\n",
+        header,
+        version,
+        body,
+        checksum(body)
+    );
+    fs::write(path, content).map_err(|x| format!("Can't write {} : {}", path, x))
+}
+
+fn read_file(path: &str) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|x| format!("Can't read {} : {}", path, x))
+}
+
+// checks the header and the checksum, and returns the body of the file
+fn check_content<'a>(content: &'a str, header: &str, version: u32) -> Result<&'a str, String> {
+    let header_end = content.find('\n').ok_or("Empty save file")?;
+    let mut words = content[..header_end].split_whitespace();
+    if words.next() != Some(header) {
+        return Err(format!("Not a {} file", header));
+    }
+    match words.next().map(|x| x.parse::<u32>()) {
+        Some(Ok(v)) if v == version => (),
+        Some(Ok(v)) => {
+            return Err(format!(
+                "Unsupported {} version {} (expected {})",
+                header, v, version
+            ))
+        }
+        _ => return Err("Corrupted save file : invalid version".to_string()),
+    }
+
+    let content = content[header_end + 1..].trim_end_matches('\n');
+    let checksum_start = content.rfind('\n').map_or(0, |x| x + 1);
+    let expected = match content[checksum_start..]
+        .split_whitespace()
+        .collect::<Vec<_>>()[..]
+    {
+        ["checksum", x] => u64::from_str_radix(x, 16)
+            .map_err(|_| "Corrupted save file : invalid checksum".to_string())?,
+        _ => return Err("Corrupted save file : missing checksum".to_string()),
+    };
+    let body = &content[..checksum_start];
+    if checksum(body) != expected {
+        return Err("Corrupted save file : checksum mismatch".to_string());
+    }
+    Ok(body)
+}
+
+// FNV-1a, enough to detect accidental corruption
+fn checksum(body: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for b in body.bytes() {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+fn write_game(body: &mut String, name: &str, game: &Game) {
+    body.push_str(&format!("{}\nboats {}\n", name, game.player.boats.len()));
+    for boat in &game.player.boats {
+        let life: String = boat
+            .detailed_life
+            .iter()
+            .map(|l| if *l { '1' } else { '0' })
+            .collect();
+        body.push_str(&format!(
+            "{} {} {}\n",
+            format_placement(boat.class, boat.position, boat.direction),
+            life,
+            boat.cooldown
+        ));
+    }
+
+    // the mines left, the islands only depend on the rules
+    body.push_str("mines");
+    for (x, column) in game.terrain.iter().enumerate() {
+        for (y, terrain) in column.iter().enumerate() {
+            if *terrain == Terrain::Mine {
+                body.push(' ');
+                body.push_str(&format_position((x as u8, y as u8)));
+            }
+        }
+    }
+    body.push('\n');
+
+    body.push_str("shots\n");
+    for (column, revealed) in game.shot_boats.iter().zip(&game.revealed) {
+        let line: String = column
+            .iter()
+            .zip(revealed)
+            .map(|s| match s {
+                (None, false) => '.',
+                (None, true) => '?',
+                (Some(true), _) => 'x',
+                (Some(false), _) => 'o',
+            })
+            .collect();
+        body.push_str(&line);
+        body.push('\n');
+    }
+}
+
+fn read_rules(reader: &mut Reader) -> Result<Rules, String> {
+    match reader.expect("rules")?.as_slice() {
+        [b] => b
+            .parse::<u8>()
+            .map_err(|x| x.to_string())
+            .and_then(Rules::from_byte)
+            .map_err(|x| reader.error(&x)),
+        _ => Err(reader.error("invalid rules")),
+    }
+}
+
+fn write_map(body: &mut String, map: &Map) {
+    body.push_str(&format!("map {}\n", map.name));
+    for row in map.rows() {
+        body.push_str(&row);
+        body.push('\n');
+    }
+}
+
+fn read_map(reader: &mut Reader) -> Result<Map, String> {
+    let name = reader.expect("map")?.join(" ");
+    let mut rows = Vec::with_capacity(NB as usize);
+    for _ in 0..NB {
+        rows.push(reader.next()?);
+    }
+    Map::from_rows(&name, &rows).map_err(|x| reader.error(&x))
+}
+
+fn read_game(reader: &mut Reader, name: &str, map: &Map, rules: Rules) -> Result<Game, String> {
+    reader.expect(name)?;
+    let n = match reader.expect("boats")?.as_slice() {
+        [n] => n
+            .parse::<usize>()
+            .map_err(|_| reader.error("invalid boat count"))?,
+        _ => return Err(reader.error("invalid boat count")),
+    };
+
+    let mut boats = Vec::with_capacity(n);
+    for _ in 0..n {
+        let line = reader.next()?;
+        boats.push(read_boat(line).ok_or_else(|| reader.error("invalid boat"))?);
+    }
+
+    let mut mines = vec![];
+    for position in reader.expect("mines")? {
+        mines.push(parse_position(position).map_err(|x| reader.error(&x))?);
+    }
+
+    reader.expect("shots")?;
+    let mut shot_boats = Vec::with_capacity(NB as usize);
+    let mut revealed = Vec::with_capacity(NB as usize);
+    for _ in 0..NB {
+        let line = reader.next()?;
+        if line.len() != NB as usize {
+            return Err(reader.error(&format!("the board doesn't match the {}x{} board", NB, NB)));
+        }
+        let mut column = Vec::with_capacity(NB as usize);
+        for c in line.chars() {
+            column.push(match c {
+                '.' | '?' => None,
+                'x' => Some(true),
+                'o' => Some(false),
+                _ => return Err(reader.error(&format!("invalid shot '{}'", c))),
+            });
+        }
+        shot_boats.push(column);
+        revealed.push(line.chars().map(|c| c == '?').collect());
+    }
+
+    let mut game = Game::with_map(map, Player::new(boats), rules).map_err(|x| reader.error(&x))?;
+    game.shot_boats = shot_boats;
+    game.revealed = revealed;
+    for (x, y) in mines {
+        game.terrain[x as usize][y as usize] = Terrain::Mine;
+    }
+    Ok(game)
+}
+
+fn write_records(body: &mut String, records: &[Record]) {
+    body.push_str(&format!("moves {}\n", records.len()));
+    for record in records {
+        body.push_str(&format!("{} {}\n", record.time.as_millis(), record.action));
+    }
+}
+
+fn read_records(reader: &mut Reader) -> Result<Vec<Record>, String> {
+    let n = match reader.expect("moves")?.as_slice() {
+        [n] => n
+            .parse::<usize>()
+            .map_err(|_| reader.error("invalid move count"))?,
+        _ => return Err(reader.error("invalid move count")),
+    };
+
+    let mut records = Vec::with_capacity(n);
+    for _ in 0..n {
+        let line = reader.next()?;
+        records.push(read_record(line).ok_or_else(|| reader.error("invalid move"))?);
+    }
+    Ok(records)
+}
+
+fn read_record(line: &str) -> Option<Record> {
+    let mut words = line.splitn(3, ' ');
+    let time = Duration::from_millis(words.next()?.parse().ok()?);
+    let player = match words.next()? {
+        "player" => true,
+        "opponent" => false,
+        _ => return None,
+    };
+    let action = words.next()?;
+    let action = if let Ok((position, shot)) = parse_mine(action) {
+        Move::Mine {
+            player,
+            position,
+            shot,
+        }
+    } else if let Ok((position, shot)) = parse_attack(action) {
+        Move::Attack {
+            player,
+            position,
+            shot,
+        }
+    } else if let Ok((position, occupied)) = parse_reveal(action) {
+        Move::Reveal {
+            player,
+            position,
+            occupied,
+        }
+    } else if let Ok((ability, position)) = parse_ability(action) {
+        Move::Ability {
+            player,
+            ability,
+            position,
+        }
+    } else if let Ok((class, movement)) = parse_movement(action) {
+        Move::Shift {
+            player,
+            class,
+            movement,
+        }
+    } else {
+        let (class, position, direction) = parse_placement(action).ok()?;
+        Move::Place {
+            player,
+            class,
+            position,
+            direction,
+        }
+    };
+    Some(Record { time, action })
+}
+
+fn read_boat(line: &str) -> Option<Boat> {
+    let (line, cooldown) = line.split_at(line.rfind(' ')?);
+    let (placement, life) = line.split_at(line.rfind(' ')?);
+    let (class, position, direction) = parse_placement(placement).ok()?;
+    let mut boat = Boat::new(class, position, direction);
+    boat.cooldown = cooldown.trim_start().parse().ok()?;
+    let life = life.trim_start();
+    if life.len() != boat.max_life() as usize {
+        return None;
+    }
+    for (i, l) in life.chars().enumerate() {
+        boat.detailed_life[i] = match l {
+            '1' => true,
+            '0' => false,
+            _ => return None,
+        };
+    }
+    Some(boat)
+}
+
+// line reader keeping track of the line number for error messages
+struct Reader<'a> {
+    lines: Enumerate<Lines<'a>>,
+    line: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn next(&mut self) -> Result<&'a str, String> {
+        let (i, line) = self
+            .lines
+            .next()
+            .ok_or_else(|| "Corrupted save file : unexpected end of file".to_string())?;
+        // the header line isn't part of the body
+        self.line = i + 2;
+        Ok(line)
+    }
+
+    // reads a line starting with the given keyword and returns the following words
+    fn expect(&mut self, keyword: &str) -> Result<Vec<&'a str>, String> {
+        let line = self.next()?;
+        let mut words = line.split_whitespace();
+        if words.next() != Some(keyword) {
+            return Err(self.error(&format!("expected '{}'", keyword)));
+        }
+        Ok(words.collect())
+    }
+
+    fn end(&mut self) -> Result<(), String> {
+        match self.next() {
+            Ok(line) => Err(self.error(&format!("unexpected content '{}'", line))),
+            Err(_) => Ok(()),
+        }
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("Corrupted save file : line {} : {}", self.line, message)
+    }
+}
@@ -1,7 +1,8 @@
 use sdl2::pixels::Color;
-use sdl2::rect::Point;
+use sdl2::rect::{Point, Rect};
 use sdl2::render::Canvas;
 use sdl2::video::Window;
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
 #[macro_export]
 macro_rules! in_board {
@@ -34,6 +35,28 @@ macro_rules! result_map {
     };
 }
 
+thread_local! {
+    // the half width of each row of the filled circles by radius, computed once
+    static SPANS: RefCell<HashMap<i32, Rc<Vec<i32>>>> = RefCell::new(HashMap::new());
+}
+
+fn spans(r: i32) -> Rc<Vec<i32>> {
+    SPANS.with(|spans| {
+        spans
+            .borrow_mut()
+            .entry(r)
+            .or_insert_with(|| {
+                Rc::new(
+                    (0..r)
+                        .map(|j| ((r * r - j * j) as f64).sqrt() as i32)
+                        .collect(),
+                )
+            })
+            .clone()
+    })
+}
+
+// drawn with a single call, one rectangle for each row
 pub fn fill_circle(
     canvas: &mut Canvas<Window>,
     color: Color,
@@ -41,22 +64,68 @@ pub fn fill_circle(
     y: i32,
     r: i32,
 ) -> Result<(), String> {
+    if r <= 0 {
+        return Ok(());
+    }
+    let mut rects = Vec::with_capacity(2 * r as usize);
+    for (j, &w) in spans(r).iter().enumerate() {
+        let width = (2 * w + 1) as u32;
+        rects.push(Rect::new(x - w, y + j as i32, width, 1));
+        if j > 0 {
+            rects.push(Rect::new(x - w, y - j as i32, width, 1));
+        }
+    }
     canvas.set_draw_color(color);
-    for i in 0..r {
-        let mut j = 0;
-        while i >= j && (i * i) + (j * j) <= (r * r) {
-            canvas.draw_point(Point::new(x + i, y + j))?;
-            canvas.draw_point(Point::new(x + i, y - j))?;
-            canvas.draw_point(Point::new(x - i, y + j))?;
-            canvas.draw_point(Point::new(x - i, y - j))?;
-            canvas.draw_point(Point::new(x + j, y + i))?;
-            canvas.draw_point(Point::new(x + j, y - i))?;
-            canvas.draw_point(Point::new(x - j, y + i))?;
-            canvas.draw_point(Point::new(x - j, y - i))?;
+    canvas.fill_rects(&rects)
+}
 
-            j += 1;
+// the outline of a circle, drawn with a single call
+pub fn draw_circle(
+    canvas: &mut Canvas<Window>,
+    color: Color,
+    x: i32,
+    y: i32,
+    r: i32,
+) -> Result<(), String> {
+    let mut points = vec![];
+    let (mut i, mut j) = (r, 0);
+    let mut error = 1 - r;
+    while i >= j {
+        points.extend_from_slice(&[
+            Point::new(x + i, y + j),
+            Point::new(x + i, y - j),
+            Point::new(x - i, y + j),
+            Point::new(x - i, y - j),
+            Point::new(x + j, y + i),
+            Point::new(x + j, y - i),
+            Point::new(x - j, y + i),
+            Point::new(x - j, y - i),
+        ]);
+        j += 1;
+        if error < 0 {
+            error += 2 * j + 1;
+        } else {
+            i -= 1;
+            error += 2 * (j - i) + 1;
         }
     }
+    canvas.set_draw_color(color);
+    canvas.draw_points(&points[..])
+}
+
+// an X of the given half width, 3 pixels thick
+pub fn draw_cross(
+    canvas: &mut Canvas<Window>,
+    color: Color,
+    x: i32,
+    y: i32,
+    r: i32,
+) -> Result<(), String> {
+    canvas.set_draw_color(color);
+    for d in -1..=1 {
+        canvas.draw_line(Point::new(x - r + d, y - r), Point::new(x + r + d, y + r))?;
+        canvas.draw_line(Point::new(x - r + d, y + r), Point::new(x + r + d, y - r))?;
+    }
     Ok(())
 }
 
@@ -108,3 +177,9 @@ pub const YELLOW: Color = Color {
     b: 0,
     a: 0,
 };
+pub const GREY: Color = Color {
+    r: 192,
+    g: 192,
+    b: 192,
+    a: 0,
+};
@@ -0,0 +1,188 @@
+// short effects played on the boards after each shot : a projectile falling on the cell, then a
+// splash for a miss, an explosion for a hit and the boat sinking once it is destroyed,
+// they are only drawn over the boards so the game goes on while they are played
+use crate::model::game::Shot;
+use crate::utils::*;
+use crate::view::{game::SIZE, sprites::SpriteRenderer, theme::theme, FRAME_MS};
+use sdl2::{rect::Rect, render::Canvas, video::Window};
+use std::{env, time::Duration};
+
+// the number of frames played in each turn of the loop of view::run, 0 disables the animations
+const SPEED_VARIABLE: &str = "BATTLESHIP_ANIMATION_SPEED";
+// the durations in frames, a frame lasts about 30 ms at normal speed
+const FALL_FRAMES: f32 = 10.;
+const SPLASH_FRAMES: f32 = 15.;
+const EXPLOSION_FRAMES: f32 = 15.;
+const SINKING_FRAMES: f32 = 30.;
+const SALVO_DELAY: f32 = 5.; // between the shots of a salvo
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Effect {
+    Splash,
+    Explosion,
+    Sinking, // of a segment of the boat
+}
+
+impl Effect {
+    fn frames(self) -> f32 {
+        match self {
+            Effect::Splash => FALL_FRAMES + SPLASH_FRAMES,
+            Effect::Explosion => FALL_FRAMES + EXPLOSION_FRAMES,
+            Effect::Sinking => SINKING_FRAMES,
+        }
+    }
+}
+
+struct Animation {
+    effect: Effect,
+    cell: (i32, i32), // the top left corner of the cell in the panel
+    frame: f32,       // negative until the animation starts
+}
+
+pub struct Animations {
+    list: Vec<Animation>,
+    speed: f32,
+    delay: f32, // before the next shot, so that the shots of a salvo follow each other
+}
+
+impl Animations {
+    pub fn new() -> Self {
+        let speed = match env::var(SPEED_VARIABLE) {
+            Ok(s) => s.parse::<f32>().unwrap_or_else(|_| {
+                println!(
+                    "Invalid {} '{}', the normal speed is used",
+                    SPEED_VARIABLE, s
+                );
+                1.
+            }),
+            Err(_) => 1.,
+        };
+        Animations {
+            list: vec![],
+            speed: speed.max(0.),
+            delay: 0.,
+        }
+    }
+
+    // the duration of this number of frames
+    fn duration(&self, frames: f32) -> Duration {
+        Duration::from_millis((frames / self.speed) as u64 * FRAME_MS)
+    }
+
+    // the shot at the position of the board drawn at the offset, with the cells of the boat
+    // it sank if any, returns when the projectile is fired and when it lands
+    pub fn add_shot(
+        &mut self,
+        offset: (i32, i32),
+        p: (u8, u8),
+        shot: Shot,
+        sunk: &[(u8, u8)],
+    ) -> (Duration, Duration) {
+        if self.speed == 0. {
+            return (Duration::from_millis(0), Duration::from_millis(0));
+        }
+        let times = (
+            self.duration(self.delay),
+            self.duration(self.delay + FALL_FRAMES),
+        );
+        let cell = |p: (u8, u8)| (offset.0 + p.0 as i32 * SIZE, offset.1 + p.1 as i32 * SIZE);
+        let effect = if shot.is_hit() || shot == Shot::Mine {
+            Effect::Explosion
+        } else {
+            Effect::Splash
+        };
+        self.list.push(Animation {
+            effect,
+            cell: cell(p),
+            frame: -self.delay,
+        });
+        for &q in sunk {
+            self.list.push(Animation {
+                effect: Effect::Sinking,
+                cell: cell(q),
+                frame: -self.delay - effect.frames(),
+            });
+        }
+        self.delay += SALVO_DELAY;
+        times
+    }
+
+    // called each turn of the loop
+    pub fn tick(&mut self) {
+        let speed = self.speed;
+        for animation in &mut self.list {
+            animation.frame += speed;
+        }
+        self.list.retain(|a| a.frame < a.effect.frames());
+        self.delay = (self.delay - speed).max(0.);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn skip(&mut self) {
+        self.list.clear();
+        self.delay = 0.;
+    }
+
+    pub fn render(&self, canvas: &mut Canvas<Window>) -> Result<(), String> {
+        let theme = theme();
+        for animation in self.list.iter().filter(|a| a.frame >= 0.) {
+            let (x, y) = animation.cell;
+            let (cx, cy) = (x + SIZE / 2, y + SIZE / 2);
+            let t = animation.frame;
+            if animation.effect != Effect::Sinking && t < FALL_FRAMES {
+                // the result is hidden until the projectile lands
+                canvas.set_draw_color(theme.background);
+                canvas.fill_rect(Rect::new(
+                    x + 1,
+                    y + 1,
+                    (SIZE - 1) as u32,
+                    (SIZE - 1) as u32,
+                ))?;
+                let height = ((FALL_FRAMES - t) / FALL_FRAMES * 2. * SIZE as f32) as i32;
+                fill_circle(canvas, theme.projectile, cx, cy - height, SIZE / 10)?;
+                continue;
+            }
+            match animation.effect {
+                Effect::Splash => {
+                    let u = (t - FALL_FRAMES) / SPLASH_FRAMES;
+                    let r = (u * SIZE as f32 / 2.) as i32;
+                    draw_circle(canvas, theme.splash, cx, cy, r)?;
+                    draw_circle(canvas, theme.foam, cx, cy, r / 2)?;
+                }
+                Effect::Explosion => {
+                    let u = (t - FALL_FRAMES) / EXPLOSION_FRAMES;
+                    // grows then fades out
+                    let r = ((1. - (2. * u - 1.).powi(2)) * SIZE as f32 / 2.) as i32;
+                    fill_circle(canvas, theme.explosion, cx, cy, r)?;
+                    fill_circle(canvas, theme.flame, cx, cy, r / 2)?;
+                }
+                Effect::Sinking => {
+                    // the water rises over the segment, with bubbles above it
+                    let u = t / SINKING_FRAMES;
+                    let height = (u * (SIZE - 1) as f32) as i32;
+                    let water = Rect::new(
+                        x + 1,
+                        y + SIZE - height,
+                        (SIZE - 1) as u32,
+                        height.max(1) as u32,
+                    );
+                    // the water sprite of the cell, cut to the height of the water
+                    canvas.set_clip_rect(water);
+                    let drawn = canvas.render_water(x, y, SIZE);
+                    canvas.set_clip_rect(None);
+                    if !drawn? {
+                        canvas.set_draw_color(theme.water);
+                        canvas.fill_rect(water)?;
+                    }
+                    for (i, dx) in [-SIZE / 4, 0, SIZE / 4].iter().enumerate() {
+                        let rise = ((t as i32 + 4 * i as i32) % 10) * SIZE / 20;
+                        draw_circle(canvas, theme.foam, cx + dx, y + SIZE - height - rise, 2)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
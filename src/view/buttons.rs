@@ -1,11 +1,11 @@
-use crate::view::panel::Panel;
+use crate::view::{panel::Panel, text::TextRenderer};
 use sdl2::{pixels::Color, rect::Rect, render::Canvas, video::Window};
 
 pub struct Button {
     background: Color,
     position: Rect,
-    _text: String,
-    _text_color: Color,
+    text: String,
+    text_color: Color,
     action: Box<FnMut() -> Option<Box<Panel>>>,
 }
 
@@ -16,22 +16,31 @@ impl Button {
         w: i32,
         h: i32,
         background: Color,
-        _text: String,
-        _text_color: Color,
+        text: String,
+        text_color: Color,
         action: Box<FnMut() -> Option<Box<Panel>>>,
     ) -> Button {
         Button {
             position: Rect::new(x, y, w as u32, h as u32),
             background,
-            _text,
-            _text_color,
+            text,
+            text_color,
             action,
         }
     }
 
     pub fn render(&self, canvas: &mut Canvas<Window>) -> Result<(), String> {
         canvas.set_draw_color(self.background);
-        canvas.fill_rect(self.position)
+        canvas.fill_rect(self.position)?;
+        canvas.render_text(&self.text, self.text_color, self.position)
+    }
+
+    pub fn set_background(&mut self, background: Color) {
+        self.background = background;
+    }
+
+    pub fn set_text(&mut self, text: String) {
+        self.text = text;
     }
 
     pub fn contains_point<P: Into<(i32, i32)>>(&self, point: P) -> bool {
@@ -1,10 +1,16 @@
 // multiplayer connection panel
-use crate::network::{create_host_socket, find_host, wait_client};
+use crate::model::{game::Game, rules::Rules, terrain::Map};
+use crate::network::{create_host_socket, find_host, handshake, wait_client};
 use crate::utils::*;
 use crate::view::{
     buttons::Button,
-    creation::CreationPanel,
+    creation::{CreationPanel, Opponent},
+    lobby::LobbyPanel,
+    maps::MapSelector,
     panel::{Panel, TEXT_COLOR},
+    rules::RulesSelector,
+    text::TextRenderer,
+    NB,
 };
 use sdl2::{
     event::{
@@ -12,18 +18,27 @@ use sdl2::{
         Event::{KeyUp, MouseButtonUp},
     },
     keyboard::Keycode,
-    mouse::{MouseButton, MouseState},
+    mouse::MouseButton,
+    rect::Rect,
     render::Canvas,
     video::Window,
 };
-use std::{net::TcpListener, time::Duration};
+use std::net::TcpListener;
+
+const ADDRESS_X: i32 = 400;
+const ADDRESS_WIDTH: u32 = 500;
 
 pub struct ConnectPanel {
     buttons: Vec<Button>,
     connect_button: Button,
     host_button: Button,
+    host_party_button: Button,
+    join_party_button: Button,
     address: String,
     host_socket: Option<TcpListener>,
+    rules: RulesSelector,  // the rules of the game if hosting it
+    map: MapSelector,      // the map of the game if hosting it
+    error: Option<String>, // why the party couldn't be hosted, until the next click or key
 }
 
 impl ConnectPanel {
@@ -48,17 +63,44 @@ impl ConnectPanel {
                 100,
                 100,
                 MAGENTA,
-                "Connect".to_string(),
+                "Host".to_string(),
+                TEXT_COLOR,
+                Box::new(|| None),
+            ),
+            host_party_button: Button::new(
+                0,
+                200,
+                100,
+                100,
+                GREEN,
+                "Host party".to_string(),
+                TEXT_COLOR,
+                Box::new(|| None),
+            ),
+            join_party_button: Button::new(
+                0,
+                300,
+                100,
+                100,
+                CYAN,
+                "Join party".to_string(),
                 TEXT_COLOR,
                 Box::new(|| None),
             ),
             address,
             host_socket: None,
+            rules: RulesSelector::new(150, 0, Rules::default()),
+            map: MapSelector::new(150, 190, Map::new(NB as usize, NB as usize)),
+            error: None,
         }
     }
 }
 
 impl Panel for ConnectPanel {
+    fn title(&self) -> &str {
+        "Multiplayer"
+    }
+
     fn button_vec_mut(&mut self) -> &mut Vec<Button> {
         &mut self.buttons
     }
@@ -68,6 +110,9 @@ impl Panel for ConnectPanel {
     }
 
     fn manage_event(&mut self, event: Event) -> Result<Option<Box<Panel>>, String> {
+        if let MouseButtonUp { .. } | KeyUp { .. } = event {
+            self.error = None;
+        }
         match event {
             MouseButtonUp {
                 mouse_btn: MouseButton::Left,
@@ -83,20 +128,48 @@ impl Panel for ConnectPanel {
                     }
                 }
                 if self.connect_button.contains_point((x, y)) {
-                    match find_host(self.address.as_str()) {
-                        Ok(sock) => {
-                            sock.set_read_timeout(Some(Duration::from_nanos(1)))
-                                .map_err(|x| x.to_string())?;
-                            return Ok(Some(Box::new(CreationPanel::new(Some((sock, false))))));
+                    match find_host(self.address.as_str()).and_then(|mut sock| {
+                        let map = Map::new(NB as usize, NB as usize);
+                        let (rules, map) = handshake(&mut sock, false, Rules::default(), &map)?;
+                        Ok((sock, rules, map))
+                    }) {
+                        Ok((sock, rules, map)) => {
+                            return Ok(Some(Box::new(CreationPanel::new(
+                                Opponent::Network(sock, false),
+                                rules,
+                                map,
+                            ))));
                         }
                         Err(e) => {
                             println!("{}", e.to_string());
                         }
                     }
                 }
+                if self.join_party_button.contains_point((x, y)) {
+                    match LobbyPanel::join(self.address.as_str()) {
+                        Ok(lobby) => return Ok(Some(Box::new(lobby))),
+                        Err(e) => println!("{}", e),
+                    }
+                }
+                if self.host_socket.is_none() && self.host_party_button.contains_point((x, y)) {
+                    match LobbyPanel::host(self.rules.rules, self.map.map().clone()) {
+                        Ok(lobby) => return Ok(Some(Box::new(lobby))),
+                        Err(e) => {
+                            println!("{}", e);
+                            self.error = Some(e);
+                        }
+                    }
+                }
+                if self.host_socket.is_none() && (self.rules.click(x, y) || self.map.click(x, y)) {
+                    self.error = Game::fits(self.map.map(), self.rules.rules).err();
+                    return Ok(None);
+                }
                 if self.host_button.contains_point((x, y)) {
                     if let None = self.host_socket {
-                        self.host_socket = Some(create_host_socket()?);
+                        match Game::fits(self.map.map(), self.rules.rules) {
+                            Ok(()) => self.host_socket = Some(create_host_socket()?),
+                            Err(e) => self.error = Some(e),
+                        }
                     }
                 }
             }
@@ -139,7 +212,7 @@ impl Panel for ConnectPanel {
         Ok(None)
     }
 
-    fn render(&self, canvas: &mut Canvas<Window>, _mouse_state: MouseState) -> Result<(), String> {
+    fn render(&self, canvas: &mut Canvas<Window>, _mouse: (i32, i32)) -> Result<(), String> {
         canvas.set_draw_color(WHITE);
         canvas.fill_rect(None)?;
         for button in &self.buttons {
@@ -147,14 +220,47 @@ impl Panel for ConnectPanel {
         }
         if let None = self.host_socket {
             self.host_button.render(canvas)?;
+            self.host_party_button.render(canvas)?;
+            self.rules.render(canvas)?;
+            self.map.render(canvas)?;
         }
-        self.connect_button.render(canvas)
+        self.join_party_button.render(canvas)?;
+        self.connect_button.render(canvas)?;
+
+        // the address typed by the player, or the state of the host
+        let address = Rect::new(ADDRESS_X, 0, ADDRESS_WIDTH, 50);
+        canvas.set_draw_color(BLACK);
+        canvas.draw_rect(address)?;
+        let text = if let Some(error) = &self.error {
+            error.clone()
+        } else if self.host_socket.is_some() {
+            "Waiting for a client...".to_string()
+        } else if self.address.is_empty() {
+            "Type the address of the host".to_string()
+        } else {
+            format!("Address : {}", self.address)
+        };
+        let color = if self.error.is_some() {
+            RED
+        } else {
+            TEXT_COLOR
+        };
+        canvas.render_text(&text, color, address)
     }
 
     fn do_loop(&mut self) -> Result<Option<Box<Panel>>, String> {
         if let Some(host_socket) = &self.host_socket {
-            if let Some(sock) = wait_client(&host_socket) {
-                return Ok(Some(Box::new(CreationPanel::new(Some((sock, true))))));
+            if let Some(mut sock) = wait_client(&host_socket) {
+                match handshake(&mut sock, true, self.rules.rules, self.map.map()) {
+                    Ok((rules, map)) => {
+                        return Ok(Some(Box::new(CreationPanel::new(
+                            Opponent::Network(sock, true),
+                            rules,
+                            map,
+                        ))))
+                    }
+                    Err(e) => println!("{}", e),
+                }
             }
         }
         Ok(None)
@@ -1,17 +1,25 @@
 use crate::model::{
-    boat::{Boat, Class},
+    boat::{Boat, Class, FLEET},
     direction::Direction,
     game::{Game, GameType},
+    party::Party,
     player::Player,
+    replay::Replay,
+    rules::Rules,
+    terrain::Map,
 };
+use crate::save::{list_layouts, load_layout, next_layout_name, save_layout};
 use crate::utils::*;
 use crate::view::{
     buttons::Button,
-    game::{GamePanel, BOARD_WIDTH, SIZE},
+    game::{GamePanel, BOARD_WIDTH, LABEL_SIZE, SIZE},
     game_renderer::GameRenderer,
-    menu::MenuPanel,
+    maps::MapSelector,
     panel::{Panel, TEXT_COLOR},
-    NB, WIDTH,
+    rules::RulesSelector,
+    text::TextRenderer,
+    theme::theme,
+    HEIGHT, NB, WIDTH,
 };
 use sdl2::{
     event::{
@@ -19,16 +27,24 @@ use sdl2::{
         Event::{KeyUp, MouseButtonDown, MouseButtonUp},
     },
     keyboard::Keycode,
-    mouse::{MouseButton, MouseState},
+    mouse::MouseButton,
+    rect::Rect,
     render::Canvas,
     video::Window,
 };
-use std::{mem::swap, net::TcpStream};
+use std::{
+    mem::{replace, swap},
+    net::TcpStream,
+    path::PathBuf,
+};
 
 const OFFSET_BOARD_X: i32 = (WIDTH - BOARD_WIDTH) / 2;
-const OFFSET_BOARD_Y: i32 = 0;
+const OFFSET_BOARD_Y: i32 = LABEL_SIZE;
 const OFFSET_PB_X: i32 = WIDTH - 6 * SIZE;
 const OFFSET_PB_Y: i32 = SIZE;
+const LAYOUT_BUTTON_Y: i32 = 260;
+const LAYOUT_BUTTON_HEIGHT: i32 = 40;
+const LAYOUT_BUTTON_SPACE: i32 = 50;
 
 macro_rules! get_order {
     ($x : expr) => {
@@ -42,28 +58,57 @@ macro_rules! get_order {
     };
 }
 
+// who the fleet being placed will play against
+pub enum Opponent {
+    Ai,
+    Network(TcpStream, bool), // the socket and whether this player starts
+    Local(Option<Game>),      // the game of the first player, once their fleet is placed
+    Party(Option<Party>),     // taken when the game starts
+}
+
 // game's creation panel
 pub struct CreationPanel {
     buttons: Vec<Button>,
-    board: Vec<Vec<Option<u8>>>,
-    player: Player,
+    game: Game,                       // the placed boats
     pending_boats: Vec<Option<Boat>>, // boats that haven't been placed yet
-    network: Option<(TcpStream, bool)>,
+    opponent: Opponent,
+    rules: RulesSelector, // only editable by the first player, network games use the rules of the host
+    map: MapSelector,     // editable like the rules
     start_button: Button,
+    save_layout_button: Button,
+    layout_buttons: Vec<(Button, PathBuf)>, // one button for each saved layout
     selected: Option<Boat>,
+    error: Option<String>, // why the game can't start with the rules and the map
 }
 
 impl CreationPanel {
-    pub fn new(network: Option<(TcpStream, bool)>) -> Self {
-        let pending_boats = vec![
-            Some(Boat::new(Class::Carrier, (0, 0), Direction::Right)),
-            Some(Boat::new(Class::Battleship, (0, 0), Direction::Right)),
-            Some(Boat::new(Class::Cruiser, (0, 0), Direction::Right)),
-            Some(Boat::new(Class::Submarine, (0, 0), Direction::Right)),
-            Some(Boat::new(Class::Destroyer, (0, 0), Direction::Right)),
-        ];
-        CreationPanel {
-            buttons: vec![],
+    pub fn new(opponent: Opponent, rules: Rules, map: Map) -> Self {
+        let pending_boats = FLEET
+            .iter()
+            .map(|class| Some(CreationPanel::pending_boat(*class, rules)))
+            .collect();
+        let mut buttons = vec![];
+        // tells which player is placing their fleet
+        let label = match &opponent {
+            Opponent::Local(None) => Some((CYAN, "Player 1".to_string())),
+            Opponent::Local(Some(_)) => Some((MAGENTA, "Player 2".to_string())),
+            Opponent::Party(Some(party)) => Some((YELLOW, format!("Player {}", party.id + 1))),
+            _ => None,
+        };
+        if let Some((color, text)) = label {
+            buttons.push(Button::new(
+                OFFSET_PB_X,
+                OFFSET_PB_Y + 6 * SIZE,
+                5 * SIZE,
+                SIZE,
+                color,
+                text,
+                TEXT_COLOR,
+                Box::new(|| None),
+            ));
+        }
+        let mut panel = CreationPanel {
+            buttons,
             start_button: Button::new(
                 0,
                 0,
@@ -74,34 +119,187 @@ impl CreationPanel {
                 TEXT_COLOR,
                 Box::new(|| None),
             ),
-            board: vec![vec![None; NB as usize]; NB as usize],
-            player: Player::new(Vec::new()),
+            save_layout_button: Button::new(
+                0,
+                100,
+                100,
+                100,
+                GREEN,
+                "Save layout".to_string(),
+                TEXT_COLOR,
+                Box::new(|| None),
+            ),
+            layout_buttons: vec![],
+            game: Game::empty(&map, rules),
             pending_boats,
-            network,
+            opponent,
+            rules: RulesSelector::new(150, 0, rules),
+            error: Game::fits(&map, rules).err(),
+            map: MapSelector::new(150, 190, map),
             selected: None,
+        };
+        panel.refresh_layouts();
+        panel
+    }
+
+    fn refresh_layouts(&mut self) {
+        // as many as fit below the rules and the map, down to the bottom of the panel
+        let max = (HEIGHT - LAYOUT_BUTTON_Y) / LAYOUT_BUTTON_SPACE;
+        self.layout_buttons = list_layouts()
+            .into_iter()
+            .take(max as usize)
+            .enumerate()
+            .map(|(i, (path, shared))| {
+                (
+                    Button::new(
+                        0,
+                        LAYOUT_BUTTON_Y + i as i32 * LAYOUT_BUTTON_SPACE,
+                        OFFSET_BOARD_X - SIZE,
+                        LAYOUT_BUTTON_HEIGHT,
+                        if shared { MAGENTA } else { CYAN },
+                        path.file_stem()
+                            .map_or(String::new(), |x| x.to_string_lossy().to_string()),
+                        TEXT_COLOR,
+                        Box::new(|| None),
+                    ),
+                    path,
+                )
+            })
+            .collect();
+    }
+
+    // a boat not placed yet, with the hull given by the rules
+    fn pending_boat(class: Class, rules: Rules) -> Boat {
+        let mut boat = Boat::new(class, (0, 0), Direction::Right);
+        boat.hull = class.hull(rules.shaped_hulls);
+        boat
+    }
+
+    // replaces the placed boats by the ones of the layout
+    fn use_layout(&mut self, game: Game) {
+        self.game = game;
+        self.selected = None;
+        for boat in &mut self.pending_boats {
+            *boat = None;
+        }
+    }
+
+    // places the placed boats again with the new rules or map,
+    // they go back to the pending ones if they don't fit anymore
+    fn set_rules(&mut self, rules: Rules) {
+        let mut player = Player::new(vec![]);
+        swap(&mut player, &mut self.game.player);
+        let classes: Vec<Class> = player.boats.iter().map(|b| b.class).collect();
+        let map = self.map.map();
+        self.game = match Game::with_map(map, player, rules) {
+            Ok(game) => game,
+            Err(e) => {
+                println!("{}, the boats must be placed again", e);
+                for class in classes {
+                    let boat = Boat::new(class, (0, 0), Direction::Right);
+                    let i = get_order!(boat);
+                    self.pending_boats[i] = Some(boat);
+                }
+                Game::empty(map, rules)
+            }
+        };
+        self.error = Game::fits(map, rules).err();
+        for boat in self
+            .pending_boats
+            .iter_mut()
+            .chain(Some(&mut self.selected))
+        {
+            if let Some(boat) = boat {
+                boat.hull = boat.class.hull(rules.shaped_hulls);
+            }
         }
     }
 
-    fn into_game_type(&mut self) -> Result<GameType, String> {
-        let mut player2 = Player::new(vec![]);
-        swap(&mut player2, &mut self.player);
+    fn rules_editable(&self) -> bool {
+        match self.opponent {
+            Opponent::Ai | Opponent::Local(None) => true,
+            _ => false,
+        }
+    }
 
-        Ok(match &self.network {
-            Some((socket, player)) => GameType::Network {
-                game: Game::new(NB as usize, NB as usize, player2)?,
-                player: *player,
-                socket: socket.try_clone().unwrap(),
-            },
-            None => GameType::Ai {
-                game: Game::new(NB as usize, NB as usize, player2)?,
-                opponent: Game::create_ai_game()?,
-                player: true,
-            },
-        })
+    // the game with the placed boats, the other ones are placed randomly
+    fn take_game(&mut self) -> Result<Game, String> {
+        self.game
+            .add_random_fleet()
+            .map_err(|e| format!("{}, move the placed boats", e))?;
+        self.game.add_random_mines();
+        let empty = Game::empty(self.map.map(), self.rules.rules);
+        Ok(replace(&mut self.game, empty))
+    }
+
+    fn start(&mut self) -> Result<Box<Panel>, String> {
+        let rules = self.rules.rules;
+        let map = self.map.map().clone();
+        let game = self.take_game()?;
+        let mut replay = Replay::new(rules, map.clone());
+
+        let game_type = match &mut self.opponent {
+            Opponent::Network(socket, player) => {
+                replay.place_fleet(true, &game.player);
+                GameType::Network {
+                    game,
+                    player: *player,
+                    socket: socket.try_clone().map_err(|x| x.to_string())?,
+                    buffer: vec![],
+                    replay,
+                    shadow: Game::opponent_view(&map, rules),
+                }
+            }
+            Opponent::Ai => {
+                let opponent = Game::create_ai_game(&map, rules)?;
+                replay.place_fleet(true, &game.player);
+                replay.place_fleet(false, &opponent.player);
+                GameType::Ai {
+                    game,
+                    opponent,
+                    player: true,
+                    replay,
+                }
+            }
+            // the second player places their fleet in a new panel
+            Opponent::Local(None) => {
+                return Ok(Box::new(CreationPanel::new(
+                    Opponent::Local(Some(game)),
+                    rules,
+                    map,
+                )))
+            }
+            Opponent::Local(first) => {
+                let first = first.take().unwrap();
+                replay.place_fleet(true, &first.player);
+                replay.place_fleet(false, &game.player);
+                GameType::Local {
+                    game: first,
+                    opponent: game,
+                    player: true,
+                    replay,
+                }
+            }
+            Opponent::Party(party) => {
+                let mut party = party.take().ok_or("The party already started")?;
+                party.share_fleet(&game.player)?;
+                replay.place_fleet(true, &game.player);
+                GameType::Party {
+                    game,
+                    party,
+                    replay,
+                }
+            }
+        };
+        Ok(Box::new(GamePanel::new(game_type)))
     }
 }
 
 impl Panel for CreationPanel {
+    fn title(&self) -> &str {
+        "Fleet placement"
+    }
+
     fn button_vec_mut(&mut self) -> &mut Vec<Button> {
         &mut self.buttons
     }
@@ -118,15 +316,47 @@ impl Panel for CreationPanel {
                 y,
                 ..
             } => {
+                if self.rules_editable() && (self.rules.click(x, y) || self.map.click(x, y)) {
+                    self.set_rules(self.rules.rules);
+                    return Ok(None);
+                }
+
+                // click on a layout button
+                if self.save_layout_button.contains_point((x, y)) {
+                    match save_layout(&self.game.player, &next_layout_name()) {
+                        Ok(_) => self.refresh_layouts(),
+                        Err(e) => println!("{}", e),
+                    }
+                    return Ok(None);
+                }
+                let mut layout = None;
+                for (button, path) in &self.layout_buttons {
+                    if button.contains_point((x, y)) {
+                        layout = Some(load_layout(path, self.map.map(), self.rules.rules));
+                    }
+                }
+                match layout {
+                    Some(Ok(game)) => {
+                        self.use_layout(game);
+                        return Ok(None);
+                    }
+                    Some(Err(e)) => {
+                        println!("{}", e);
+                        return Ok(None);
+                    }
+                    None => (),
+                }
+
                 // click on the start button
                 if self.start_button.contains_point((x, y)) {
-                    return Ok(Some(match self.into_game_type() {
-                        Ok(game) => Box::new(GamePanel::new(game)),
+                    match self.start() {
+                        Ok(panel) => return Ok(Some(panel)),
                         Err(err) => {
                             println!("{}", err);
-                            Box::new(MenuPanel::new())
+                            self.error = Some(err);
+                            return Ok(None);
                         }
-                    }));
+                    }
                 }
 
                 // drop a boat
@@ -134,47 +364,33 @@ impl Panel for CreationPanel {
                 swap(&mut boat, &mut self.selected);
                 if let Some(mut boat) = boat {
                     println!("drop the boat");
-                    let xx = ((x - OFFSET_BOARD_X) / SIZE) as usize;
-                    let yy = ((y - OFFSET_BOARD_Y) / SIZE) as usize;
-                    let dx = boat.direction.dx() as usize;
-                    let dy = boat.direction.dy() as usize;
-                    let life = boat.max_life() as usize;
-                    println!("{},{}", xx, yy);
-
-                    let mut valid = in_board!(xx, yy, NB, NB)
-                    // the head of the boat is in the board
-                        && in_board!(
-                            xx + dx * (life - 1),
-                            yy + dy * (life - 1),
-                            NB,
-                            NB
-                        );
-                    // the tail of the boat is in the board
-
-                    if valid {
-                        println!("fully in board, let's see if the position is empty");
-                        for i in 0..life {
-                            valid &= self.board[xx + i * dx][yy + i * dy] == None;
-                        }
-                    } else {
-                        println!("Not even fully in the board");
-                    }
-                    if valid {
-                        println!(
-                            "valid drop of the boat xx:{}, yy:{}, life:{}, dx:{}, dy:{}",
-                            xx, yy, life, dx, dy
-                        );
+                    let valid = if in_board!(
+                        x,
+                        y,
+                        BOARD_WIDTH,
+                        BOARD_WIDTH,
+                        OFFSET_BOARD_X,
+                        OFFSET_BOARD_Y
+                    ) {
+                        let xx = (x - OFFSET_BOARD_X) / SIZE;
+                        let yy = (y - OFFSET_BOARD_Y) / SIZE;
+                        println!("{},{}", xx, yy);
                         boat.position = (xx as u8, yy as u8);
-                        self.player.boats.push(boat);
-                        let pos = (self.player.boats.len() - 1) as u8;
-                        for i in 0..life {
-                            self.board[xx + dx * i][yy + i * dy] = Some(pos);
-                        }
+                        self.game.check_boat(&boat)
                     } else {
-                        println!("invalid drop of the boat");
-                        boat.direction = Direction::Right;
-                        let i = get_order!(boat);
-                        self.pending_boats[i] = Some(boat);
+                        Err("Not even in the board".to_string())
+                    };
+                    match valid {
+                        Ok(()) => {
+                            println!("valid drop of the boat {:?}", boat);
+                            self.game.add_boat(boat)?;
+                        }
+                        Err(e) => {
+                            println!("invalid drop of the boat : {}", e);
+                            boat.direction = Direction::Right;
+                            let i = get_order!(boat);
+                            self.pending_boats[i] = Some(boat);
+                        }
                     }
                 }
             }
@@ -186,8 +402,13 @@ impl Panel for CreationPanel {
                 mouse_btn: MouseButton::Right,
                 ..
             } => {
+                let diagonal = self.rules.rules.diagonal_boats;
                 if let Some(boat) = &mut self.selected {
-                    boat.direction = boat.direction.rotate();
+                    boat.direction = if diagonal {
+                        boat.direction.rotate_diagonal()
+                    } else {
+                        boat.direction.rotate()
+                    };
                 }
             }
             MouseButtonDown {
@@ -220,38 +441,115 @@ impl Panel for CreationPanel {
         Ok(None)
     }
 
-    fn render(&self, canvas: &mut Canvas<Window>, mouse_state: MouseState) -> Result<(), String> {
+    fn render(&self, canvas: &mut Canvas<Window>, mouse: (i32, i32)) -> Result<(), String> {
         canvas.set_draw_color(WHITE);
         canvas.fill_rect(None)?;
         for button in &self.buttons {
             button.render(canvas)?;
         }
 
+        // the empty positions where no boat can be placed
+        for x in 0..NB {
+            for y in 0..NB {
+                if self.game.forbidden((x as u8, y as u8)) {
+                    canvas.set_draw_color(GREY);
+                    canvas.fill_rect(Rect::new(
+                        OFFSET_BOARD_X + x * SIZE,
+                        OFFSET_BOARD_Y + y * SIZE,
+                        SIZE as u32,
+                        SIZE as u32,
+                    ))?;
+                }
+            }
+        }
+        canvas.render_terrain(&self.game, (OFFSET_BOARD_X, OFFSET_BOARD_Y), SIZE, false)?;
         canvas.render_board_boat(
-            &self.player,
+            &self.game.player,
+            &self.game.terrain,
             (OFFSET_BOARD_X, OFFSET_BOARD_Y),
-            (NB, NB),
             SIZE,
         )?;
+        canvas.render_labels((OFFSET_BOARD_X, OFFSET_BOARD_Y), NB as usize, SIZE)?;
         self.start_button.render(canvas)?;
+        self.save_layout_button.render(canvas)?;
+        if self.rules_editable() {
+            self.rules.render(canvas)?;
+            self.map.render(canvas)?;
+        }
+        for (button, _) in &self.layout_buttons {
+            button.render(canvas)?;
+        }
 
         if let Some(boat) = &self.selected {
-            canvas.render_boat_at(&boat, (mouse_state.x(), mouse_state.y()))?;
+            // where the boat would be dropped, green if it can be placed there
+            let (x, y) = mouse;
+            if in_board!(
+                x,
+                y,
+                BOARD_WIDTH,
+                BOARD_WIDTH,
+                OFFSET_BOARD_X,
+                OFFSET_BOARD_Y
+            ) {
+                let position = (
+                    ((x - OFFSET_BOARD_X) / SIZE) as u8,
+                    ((y - OFFSET_BOARD_Y) / SIZE) as u8,
+                );
+                let mut preview = Boat::new(boat.class, position, boat.direction);
+                preview.hull = boat.hull;
+                let valid = self.game.check_boat(&preview).is_ok();
+                let color = if valid {
+                    theme().valid
+                } else {
+                    theme().invalid
+                };
+                for (x, y) in preview.cells() {
+                    if !in_board!(x, y, NB, NB, 0, 0) {
+                        continue;
+                    }
+                    let (x, y) = (
+                        OFFSET_BOARD_X + x as i32 * SIZE,
+                        OFFSET_BOARD_Y + y as i32 * SIZE,
+                    );
+                    canvas.set_draw_color(color);
+                    for margin in 1..4 {
+                        canvas.draw_rect(Rect::new(
+                            x + margin,
+                            y + margin,
+                            (SIZE - 2 * margin) as u32,
+                            (SIZE - 2 * margin) as u32,
+                        ))?;
+                    }
+                    // the invalid positions are crossed
+                    if !valid {
+                        draw_cross(canvas, color, x + SIZE / 2, y + SIZE / 2, SIZE / 3)?;
+                    }
+                }
+            }
+            canvas.render_boat_at(&boat, (x, y), SIZE)?;
         }
 
         for i in 0..self.pending_boats.len() {
             if let Some(boat) = &self.pending_boats[i] {
+                // the hulls deeper than a row are shrunk to fit in theirs
+                let tile_size = SIZE / boat.hull.depth(boat.max_life()) as i32;
                 canvas.render_boat_at(
                     boat,
                     (
-                        OFFSET_PB_X + SIZE / 2,
-                        OFFSET_PB_Y + SIZE / 2 + i as i32 * SIZE,
+                        OFFSET_PB_X + tile_size / 2,
+                        OFFSET_PB_Y + tile_size / 2 + i as i32 * SIZE,
                     ),
+                    tile_size,
                 )?;
             }
         }
 
-        canvas.render_grid((OFFSET_PB_X, OFFSET_PB_Y), (5, 5), SIZE)?;
+        canvas.render_grid((OFFSET_PB_X, OFFSET_PB_Y), &Map::new(5, 5).terrain, SIZE)?;
+        if let Some(error) = &self.error {
+            let (width, height) = (5 * SIZE as u32, 2 * SIZE as u32);
+            let rect = Rect::new(OFFSET_PB_X, OFFSET_PB_Y + 7 * SIZE, width, height);
+            canvas.render_text(error, RED, rect)?;
+        }
         Ok(())
     }
 }
@@ -13,6 +13,10 @@ pub struct EndGamePanel {
 
 impl EndGamePanel {
     pub fn new(win: bool) -> Self {
+        EndGamePanel::with_message(if win { "You won !" } else { "You lose !" }.to_string())
+    }
+
+    pub fn with_message(message: String) -> Self {
         let n = 3;
         let width = 200;
         let height = 100;
@@ -23,11 +27,11 @@ impl EndGamePanel {
             buttons: vec![
                 Button::new(
                     h_space,
-                    2 * v_space + height,
+                    v_space,
                     width,
                     height,
                     MAGENTA,
-                    if win { "You won !" } else { "You lose !" }.to_string(),
+                    message,
                     TEXT_COLOR,
                     Box::new(|| None),
                 ),
@@ -57,6 +61,10 @@ impl EndGamePanel {
 }
 
 impl Panel for EndGamePanel {
+    fn title(&self) -> &str {
+        "End of the game"
+    }
+
     fn button_vec_mut(&mut self) -> &mut Vec<Button> {
         &mut self.buttons
     }
@@ -1,33 +1,397 @@
 // game panel
-use crate::model::game::GameType;
+use crate::model::{
+    boat::{Boat, Movement, FLEET, MOVEMENTS},
+    game::{Game, GameType, Shot},
+    replay::Move,
+};
+use crate::save::{REPLAY_FILE, SAVE_FILE};
 use crate::utils::*;
 use crate::view::{
-    buttons::Button, end_game::EndGamePanel, game_renderer::GameRenderer, panel::Panel,
+    animations::Animations,
+    buttons::Button,
+    end_game::EndGamePanel,
+    game_renderer::GameRenderer,
+    panel::{Panel, TEXT_COLOR},
+    sound::{self, Effect},
+    status::render_status,
+    theme::theme,
+    HEIGHT, WIDTH,
 };
 use crate::NB;
-use sdl2::{event::Event, mouse::MouseState, render::Canvas, video::Window};
+use sdl2::{event::Event, keyboard::Keycode, rect::Rect, render::Canvas, video::Window};
+use std::cell::Cell;
 
 pub const SIZE: i32 = 50;
 pub const DELTA: i32 = 2 * SIZE;
 pub const BOARD_WIDTH: i32 = NB * SIZE;
-pub const OFFSET_X: i32 = BOARD_WIDTH + 100;
-pub const OFFSET_Y: i32 = 0;
+pub const LABEL_SIZE: i32 = 24; // the letters above the boards and the numbers on their left
+pub const BOARD_X: i32 = LABEL_SIZE; // the board of the player, the shot board is at OFFSET_X
+pub const GAP_X: i32 = BOARD_X + BOARD_WIDTH; // the buttons between the boards
+pub const OFFSET_X: i32 = GAP_X + DELTA + LABEL_SIZE;
+pub const OFFSET_Y: i32 = LABEL_SIZE;
+const TAB_HEIGHT: i32 = 80;
+const MINI_SIZE: i32 = 8; // the tiles of the fleet of the teammate
 
 pub struct GamePanel {
     buttons: Vec<Button>,
+    save_button: Button,
+    fire_button: Button,
+    selection: Vec<(u8, u8)>, // the positions to attack with the next salvo
+    move_buttons: Vec<(Button, Movement)>,
+    ability_buttons: Vec<Button>, // one for each class of the fleet
+    selected: Option<usize>,      // the boat to move or whose ability to use
+    aiming: bool,                 // the next click on the shot board uses the ability
+    pass_button: Button,
+    turn_buttons: Vec<Button>, // hide the boards while the device is passed, one for each player
+    shown: Option<bool>,       // the player of a local game whose view is shown
+    turn_button: Button,       // shown during the turn of the main player of a party
+    target_buttons: Vec<(Button, u8)>, // the tabs of the opponents of a party
+    animations: Animations,
+    seen: usize, // the moves of the replay, or the shots of a party, already animated
+    rendered: Cell<Option<StateKey>>, // the state drawn last, only drawn again once it changes
     game: GameType,
 }
 
+// what changes the boards without any event : the number of moves or shots, the turn, the target
+// and the waiting of a party, and whether animations are played
+type StateKey = (usize, u8, u8, bool, bool);
+
+// the cells of the boat sunk at this position, on the board of the player or on the shot board
+fn sunk_cells(game: &Game, p: (u8, u8), own: bool) -> Vec<(u8, u8)> {
+    if own {
+        return game.board_boats[p.0 as usize][p.1 as usize].map_or(vec![], |i| {
+            game.player.boats[i as usize]
+                .cells()
+                .into_iter()
+                .filter(|&(x, y)| in_board!(x, y, NB, NB))
+                .map(|(x, y)| (x as u8, y as u8))
+                .collect()
+        });
+    }
+    // the hits touching the position, even diagonally
+    let mut cells = vec![p];
+    let mut i = 0;
+    while i < cells.len() {
+        let (x, y) = (cells[i].0 as i32, cells[i].1 as i32);
+        for qx in x - 1..=x + 1 {
+            for qy in y - 1..=y + 1 {
+                if !in_board!(qx, qy, NB, NB) {
+                    continue;
+                }
+                let q = (qx as u8, qy as u8);
+                if game.shot_boats[qx as usize][qy as usize] == Some(true) && !cells.contains(&q) {
+                    cells.push(q);
+                }
+            }
+        }
+        i += 1;
+    }
+    cells
+}
+
 impl GamePanel {
     pub fn new(game: GameType) -> Self {
-        GamePanel {
+        let mut panel = GamePanel {
             buttons: vec![],
+            save_button: Button::new(
+                GAP_X,
+                0,
+                DELTA,
+                DELTA,
+                GREEN,
+                "Save".to_string(),
+                TEXT_COLOR,
+                Box::new(|| None),
+            ),
+            fire_button: Button::new(
+                GAP_X,
+                DELTA,
+                DELTA,
+                DELTA,
+                RED,
+                "Fire".to_string(),
+                TEXT_COLOR,
+                Box::new(|| None),
+            ),
+            selection: vec![],
+            move_buttons: MOVEMENTS
+                .iter()
+                .enumerate()
+                .map(|(i, movement)| {
+                    (
+                        Button::new(
+                            GAP_X,
+                            (2 + i as i32) * DELTA,
+                            DELTA,
+                            DELTA,
+                            CYAN,
+                            movement.name().to_string(),
+                            TEXT_COLOR,
+                            Box::new(|| None),
+                        ),
+                        *movement,
+                    )
+                })
+                .collect(),
+            ability_buttons: FLEET
+                .iter()
+                .map(|class| {
+                    Button::new(
+                        GAP_X,
+                        5 * DELTA,
+                        DELTA,
+                        DELTA,
+                        MAGENTA,
+                        class.ability().name().to_string(),
+                        TEXT_COLOR,
+                        Box::new(|| None),
+                    )
+                })
+                .collect(),
+            selected: None,
+            aiming: false,
+            pass_button: Button::new(
+                GAP_X,
+                DELTA,
+                DELTA,
+                DELTA,
+                YELLOW,
+                "Pass".to_string(),
+                TEXT_COLOR,
+                Box::new(|| None),
+            ),
+            turn_buttons: ["Player 2", "Player 1"]
+                .iter()
+                .map(|name| {
+                    Button::new(
+                        0,
+                        0,
+                        WIDTH,
+                        HEIGHT,
+                        GREY,
+                        format!("{}, click to play", name),
+                        TEXT_COLOR,
+                        Box::new(|| None),
+                    )
+                })
+                .collect(),
+            shown: None,
+            turn_button: Button::new(
+                GAP_X,
+                0,
+                DELTA,
+                DELTA,
+                GREEN,
+                "Your turn".to_string(),
+                TEXT_COLOR,
+                Box::new(|| None),
+            ),
+            target_buttons: vec![],
+            animations: Animations::new(),
+            seen: match &game {
+                GameType::Party { party, .. } => party.shots.len(),
+                _ => game.replay().records.len(),
+            },
+            rendered: Cell::new(None),
             game,
+        };
+        if let GameType::Party { party, .. } = &panel.game {
+            // the tabs of the opponents are stacked below the fire button
+            panel.target_buttons = (0..party.alive.len() as u8)
+                .filter(|&i| party.side(i) != party.side(party.id))
+                .enumerate()
+                .map(|(slot, i)| {
+                    (
+                        Button::new(
+                            GAP_X,
+                            2 * DELTA + slot as i32 * TAB_HEIGHT,
+                            DELTA,
+                            TAB_HEIGHT,
+                            CYAN,
+                            format!("Player {}", i + 1),
+                            TEXT_COLOR,
+                            Box::new(|| None),
+                        ),
+                        i,
+                    )
+                })
+                .collect();
+            panel.refresh_targets();
+        }
+        panel
+    }
+
+    // colours the tabs of a party : the target, the other opponents and the eliminated ones
+    fn refresh_targets(&mut self) {
+        if let GameType::Party { party, .. } = &self.game {
+            for (button, i) in &mut self.target_buttons {
+                button.set_background(if *i == party.target {
+                    YELLOW
+                } else if party.alive[*i as usize] {
+                    CYAN
+                } else {
+                    GREY
+                });
+            }
+        }
+    }
+
+    // the game seen by the player in front of the device
+    fn view(&self) -> &Game {
+        self.game.player_game(self.shown != Some(false))
+    }
+
+    // whether the player of a local game has ended their turn and must pass the device
+    fn turn_ended(&self) -> bool {
+        match self.game {
+            GameType::Local { player, .. } => self.shown.map_or(false, |p| p != player),
+            _ => false,
+        }
+    }
+
+    // selects or unselects a position for the next salvo
+    fn select(&mut self, p: (u8, u8)) {
+        if let Some(i) = self.selection.iter().position(|x| *x == p) {
+            self.selection.remove(i);
+        } else if self.selection.len() < self.game.shots() && self.game.shot_board().can_target(p) {
+            self.selection.push(p);
         }
     }
+
+    // selects the surviving boat of the main player at this position
+    fn select_boat(&mut self, p: (u8, u8)) {
+        let game = self.view();
+        self.selected = game.board_boats[p.0 as usize][p.1 as usize]
+            .map(|i| i as usize)
+            .filter(|&i| !game.player.boats[i].is_dead());
+        self.aiming = false;
+    }
+
+    fn selected_boat(&self) -> Option<&Boat> {
+        self.selected.map(|i| &self.view().player.boats[i])
+    }
+
+    fn can_move(&self) -> bool {
+        self.game.rules().moving_boats && self.selected_boat().map_or(false, |b| !b.is_damaged())
+    }
+
+    fn can_use_ability(&self) -> bool {
+        self.game.rules().abilities
+            && self.game.can_attack()
+            && self
+                .selected_boat()
+                .map_or(false, |b| self.view().ability_ready(b.class).is_ok())
+    }
+
+    fn move_boat(&mut self, movement: Movement) -> Result<(), String> {
+        if let Some(boat) = self.selected_boat() {
+            self.game.move_boat(boat.class, movement)?;
+            self.selection.clear();
+        }
+        Ok(())
+    }
+
+    fn use_ability(&mut self, p: (u8, u8)) -> Result<(), String> {
+        if let Some(boat) = self.selected_boat() {
+            self.game.use_ability(boat.class, p)?;
+            self.selection.clear();
+        }
+        self.aiming = false;
+        Ok(())
+    }
+
+    fn fire(&mut self) -> Result<(), String> {
+        if self.game.can_attack() && self.selection.len() == self.game.shots() {
+            self.game.attack(&self.selection)?;
+            self.selection.clear();
+        }
+        Ok(())
+    }
+
+    fn state_key(&self) -> StateKey {
+        let animated = !self.animations.is_empty();
+        match &self.game {
+            GameType::Party { party, .. } => (
+                party.shots.len(),
+                party.turn,
+                party.target,
+                party.waiting,
+                animated,
+            ),
+            game_type => (game_type.replay().records.len(), 0, 0, false, animated),
+        }
+    }
+
+    // animates the shots played since the last call, then plays the next frame
+    fn animate_shots(&mut self) {
+        let perspective = self.shown != Some(false);
+        let mut shots = vec![]; // on the shot board or not, the position and the result
+        let board = match &self.game {
+            GameType::Party { party, .. } => {
+                for &(_, attacked, p, shot) in &party.shots[self.seen..] {
+                    if attacked == party.target || attacked == party.id {
+                        shots.push((attacked != party.id, p, shot));
+                    }
+                }
+                self.seen = party.shots.len();
+                &party.boards[party.target as usize]
+            }
+            game_type => {
+                let records = &game_type.replay().records;
+                for record in &records[self.seen..] {
+                    match record.action {
+                        Move::Attack {
+                            player,
+                            position,
+                            shot,
+                        }
+                        | Move::Mine {
+                            player,
+                            position,
+                            shot,
+                        } => shots.push((player == perspective, position, shot)),
+                        _ => (),
+                    }
+                }
+                self.seen = records.len();
+                self.view()
+            }
+        };
+        let shots: Vec<_> = shots
+            .into_iter()
+            .map(|(shot_board, p, shot)| {
+                let (offset, game) = if shot_board {
+                    ((OFFSET_X, OFFSET_Y), board)
+                } else {
+                    ((BOARD_X, OFFSET_Y), self.view())
+                };
+                let sunk = if shot == Shot::Sunk {
+                    sunk_cells(game, p, !shot_board)
+                } else {
+                    vec![]
+                };
+                (offset, p, shot, sunk)
+            })
+            .collect();
+        for (offset, p, shot, sunk) in shots {
+            let (fired, landed) = self.animations.add_shot(offset, p, shot, &sunk);
+            sound::play_after(Effect::Fire, fired);
+            let effect = match shot {
+                Shot::Sunk => Effect::Sunk,
+                Shot::Hit | Shot::Mine => Effect::Hit,
+                Shot::Miss => Effect::Miss,
+            };
+            sound::play_after(effect, landed);
+        }
+        self.animations.tick();
+    }
 }
 
 impl Panel for GamePanel {
+    fn title(&self) -> &str {
+        "Game"
+    }
+
     fn button_vec_mut(&mut self) -> &mut Vec<Button> {
         &mut self.buttons
     }
@@ -36,37 +400,243 @@ impl Panel for GamePanel {
         &self.buttons
     }
 
-    fn render(&self, canvas: &mut Canvas<Window>, _mouse_state: MouseState) -> Result<(), String> {
-        canvas.set_draw_color(WHITE);
+    fn changed(&self) -> bool {
+        !self.animations.is_empty() || self.rendered.get() != Some(self.state_key())
+    }
+
+    fn render(&self, canvas: &mut Canvas<Window>, mouse: (i32, i32)) -> Result<(), String> {
+        self.rendered.set(Some(self.state_key()));
+        canvas.set_draw_color(theme().background);
         canvas.fill_rect(None)?;
         for button in &self.buttons {
             button.render(canvas)?;
         }
-        canvas.render_game_type(&self.game)?;
+        match self.game {
+            GameType::Local { player, .. } if self.shown.is_none() => {
+                return self.turn_buttons[player as usize].render(canvas);
+            }
+            GameType::Network { .. } => canvas.render_game_type(&self.game)?,
+            GameType::Party { ref party, .. } => {
+                canvas.render_game_type(&self.game)?;
+                for (button, _) in &self.target_buttons {
+                    button.render(canvas)?;
+                }
+                if party.can_attack() {
+                    self.turn_button.render(canvas)?;
+                }
+                // the fleet of the teammate, below the tabs of the two opponents
+                if let Some(teammate) = party.teammate() {
+                    let board = &party.boards[teammate as usize];
+                    canvas.render_board_boat(
+                        &board.player,
+                        &board.terrain,
+                        (GAP_X + (DELTA - NB * MINI_SIZE) / 2, 5 * DELTA),
+                        MINI_SIZE,
+                    )?;
+                }
+            }
+            _ => {
+                canvas.render_game(self.view())?;
+                self.save_button.render(canvas)?;
+            }
+        }
+        self.animations.render(canvas)?;
+        render_status(canvas, &self.game, self.shown != Some(false))?;
+        if self.turn_ended() {
+            return self.pass_button.render(canvas);
+        }
+        if let Some(boat) = self.selected_boat() {
+            canvas.set_draw_color(theme().selection);
+            for (x, y) in boat.cells() {
+                canvas.draw_rect(Rect::new(
+                    BOARD_X + x as i32 * SIZE + 2,
+                    OFFSET_Y + y as i32 * SIZE + 2,
+                    (SIZE - 4) as u32,
+                    (SIZE - 4) as u32,
+                ))?;
+            }
+            if self.can_move() {
+                for (button, _) in &self.move_buttons {
+                    button.render(canvas)?;
+                }
+            }
+            if self.can_use_ability() {
+                self.ability_buttons[boat.class as usize].render(canvas)?;
+            }
+
+            // the targeted area under the mouse
+            let (x, y) = mouse;
+            if self.aiming && in_board!(x, y, BOARD_WIDTH, BOARD_WIDTH, OFFSET_X, OFFSET_Y) {
+                let p = (((x - OFFSET_X) / SIZE) as u8, ((y - OFFSET_Y) / SIZE) as u8);
+                canvas.set_draw_color(theme().ability);
+                for (x, y) in self.view().ability_area(boat.class, p) {
+                    canvas.draw_rect(Rect::new(
+                        OFFSET_X + x as i32 * SIZE + 2,
+                        OFFSET_Y + y as i32 * SIZE + 2,
+                        (SIZE - 4) as u32,
+                        (SIZE - 4) as u32,
+                    ))?;
+                }
+            }
+        }
+        if self.game.rules().salvo {
+            self.fire_button.render(canvas)?;
+            for p in &self.selection {
+                fill_circle(
+                    canvas,
+                    theme().selection,
+                    OFFSET_X + SIZE / 2 + p.0 as i32 * SIZE,
+                    OFFSET_Y + SIZE / 2 + p.1 as i32 * SIZE,
+                    SIZE / 5,
+                )?;
+            }
+        }
+
+        // the target under the mouse, dimmed if it can't be attacked anymore
+        let (x, y) = mouse;
+        if !self.aiming && in_board!(x, y, BOARD_WIDTH, BOARD_WIDTH, OFFSET_X, OFFSET_Y) {
+            let p = (((x - OFFSET_X) / SIZE) as u8, ((y - OFFSET_Y) / SIZE) as u8);
+            let board = self.game.shot_board();
+            if board.playable(p) {
+                canvas.set_draw_color(if board.can_target(p) {
+                    theme().valid
+                } else {
+                    GREY
+                });
+                for margin in 1..4 {
+                    canvas.draw_rect(Rect::new(
+                        OFFSET_X + p.0 as i32 * SIZE + margin,
+                        OFFSET_Y + p.1 as i32 * SIZE + margin,
+                        (SIZE - 2 * margin) as u32,
+                        (SIZE - 2 * margin) as u32,
+                    ))?;
+                }
+            }
+        }
 
         Ok(())
     }
 
     fn manage_event(&mut self, event: Event) -> Result<Option<Box<Panel>>, String> {
-        if let Event::MouseButtonUp { x, y, .. } = event {
-            println!("Mouse button up");
-            if in_board!(x, y, BOARD_WIDTH, BOARD_WIDTH, OFFSET_X, OFFSET_Y) {
-                let x = ((x - OFFSET_X) / SIZE) as u8;
-                let y = ((y - OFFSET_Y) / SIZE) as u8;
-                self.game.attack((x, y))?;
+        // the device is passed between the players of a local game
+        if let GameType::Local { player, .. } = self.game {
+            if let Event::MouseButtonUp { x, y, .. } = event {
+                if self.shown.is_none() {
+                    self.shown = Some(player);
+                    return Ok(None);
+                } else if self.turn_ended() && self.pass_button.contains_point((x, y)) {
+                    self.shown = None;
+                    self.selection.clear();
+                    self.selected = None;
+                    self.aiming = false;
+                    return Ok(None);
+                }
             }
+            if self.shown != Some(player) {
+                return Ok(None);
+            }
+        }
+
+        match event {
+            Event::MouseButtonUp { x, y, .. } => {
+                println!("Mouse button up");
+                if self.save_button.contains_point((x, y)) {
+                    if let Err(e) = self.game.save(SAVE_FILE) {
+                        println!("{}", e);
+                    }
+                } else if let Some(&(_, target)) = self
+                    .target_buttons
+                    .iter()
+                    .find(|(b, _)| b.contains_point((x, y)))
+                {
+                    if let GameType::Party { party, .. } = &mut self.game {
+                        party.set_target(target);
+                    }
+                    self.selection.clear();
+                    self.refresh_targets();
+                } else if self.game.rules().salvo && self.fire_button.contains_point((x, y)) {
+                    self.fire()?;
+                } else if let Some(&(_, movement)) = self
+                    .move_buttons
+                    .iter()
+                    .find(|(b, _)| self.can_move() && b.contains_point((x, y)))
+                {
+                    self.move_boat(movement)?;
+                } else if self.can_use_ability()
+                    // the ability buttons are all at the same place
+                    && self.ability_buttons[0].contains_point((x, y))
+                {
+                    self.aiming = !self.aiming;
+                } else if (self.game.rules().moving_boats || self.game.rules().abilities)
+                    && in_board!(x, y, BOARD_WIDTH, BOARD_WIDTH, BOARD_X, OFFSET_Y)
+                {
+                    let p = (((x - BOARD_X) / SIZE) as u8, ((y - OFFSET_Y) / SIZE) as u8);
+                    self.select_boat(p);
+                } else if in_board!(x, y, BOARD_WIDTH, BOARD_WIDTH, OFFSET_X, OFFSET_Y) {
+                    let x = ((x - OFFSET_X) / SIZE) as u8;
+                    let y = ((y - OFFSET_Y) / SIZE) as u8;
+                    if self.aiming {
+                        self.use_ability((x, y))?;
+                    } else if self.game.rules().salvo {
+                        self.select((x, y));
+                    } else {
+                        self.game.attack(&[(x, y)])?;
+                    }
+                }
+            }
+            Event::KeyUp {
+                keycode: Some(Keycode::Return),
+                ..
+            }
+            | Event::KeyUp {
+                keycode: Some(Keycode::Space),
+                ..
+            } => self.fire()?,
+            Event::KeyUp {
+                keycode: Some(Keycode::S),
+                ..
+            } => self.animations.skip(),
+            _ => (),
         }
 
         Ok(None)
     }
 
     fn do_loop(&mut self) -> Result<Option<Box<Panel>>, String> {
+        self.animate_shots();
         if let Some(b) = self.game.is_over() {
+            // the last shots are shown before the end of the game
+            if !self.animations.is_empty() {
+                return Ok(None);
+            }
+            if let Err(e) = self.game.replay().save(REPLAY_FILE) {
+                println!("{}", e);
+            }
+            // a local game is always won by the player in front of the device
+            let won = match self.game {
+                GameType::Local { .. } => true,
+                _ => b,
+            };
+            sound::play(if won { Effect::Victory } else { Effect::Defeat });
+            if let GameType::Local { .. } = self.game {
+                let winner = if b { "Player 1" } else { "Player 2" };
+                return Ok(Some(Box::new(EndGamePanel::with_message(format!(
+                    "{} won !",
+                    winner
+                )))));
+            }
             return Ok(Some(Box::new(EndGamePanel::new(b))));
         } else if let GameType::Network { .. } = self.game {
             if self.game.check_network()? {
                 return Ok(Some(Box::new(EndGamePanel::new(true))));
             }
+        } else if let GameType::Party { .. } = self.game {
+            if self.game.check_party()? {
+                return Ok(Some(Box::new(EndGamePanel::with_message(
+                    "The host left the party".to_string(),
+                ))));
+            }
+            self.refresh_targets();
         }
 
         Ok(None)
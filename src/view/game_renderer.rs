@@ -3,43 +3,71 @@ use crate::model::{
     boat::Boat,
     game::{Game, GameType},
     player::Player,
+    terrain::Terrain,
 };
 use crate::utils::*;
-use crate::view::game::{OFFSET_X, SIZE};
-use crate::NB;
-use sdl2::pixels::Color;
+use crate::view::{
+    game::{BOARD_X, LABEL_SIZE, OFFSET_X, OFFSET_Y, SIZE},
+    panel::TEXT_COLOR,
+    sprites::SpriteRenderer,
+    text::TextRenderer,
+    theme::theme,
+};
+use sdl2::rect::Rect;
 use sdl2::render::Canvas;
 use sdl2::video::Window;
 
-const HEALTHY_BOAT: Color = BLUE;
-const WOUNDED_BOAT: Color = RED;
-
 pub trait GameRenderer {
     fn render_game_type(&mut self, game_type: &GameType) -> Result<(), String>;
 
     fn render_game(&mut self, game: &Game) -> Result<(), String> {
-        self.render_board_boat(&game.player, (0, 0), (NB, NB), SIZE)?;
+        self.render_terrain(game, (BOARD_X, OFFSET_Y), SIZE, true)?;
+        self.render_board_boat(&game.player, &game.terrain, (BOARD_X, OFFSET_Y), SIZE)?;
+        self.render_labels((BOARD_X, OFFSET_Y), game.terrain.len(), SIZE)?;
         self.render_shot_board(game)
     }
 
     fn render_board_boat(
         &mut self,
         player: &Player,
+        terrain: &[Vec<Terrain>],
         offset: (i32, i32),
-        board_size: (i32, i32),
         tile_size: i32,
     ) -> Result<(), String>;
 
     fn render_shot_board(&mut self, game: &Game) -> Result<(), String>;
 
+    // the islands, and the mines if they are visible
+    fn render_terrain(
+        &mut self,
+        game: &Game,
+        offset: (i32, i32),
+        tile_size: i32,
+        mines: bool,
+    ) -> Result<(), String>;
+
+    // the outline of the cells of the board, the ones outside of the map aren't drawn
     fn render_grid(
         &mut self,
         offset: (i32, i32),
-        board_size: (i32, i32),
+        terrain: &[Vec<Terrain>],
         tile_size: i32,
     ) -> Result<(), String>;
 
-    fn render_boat_at(&mut self, boat: &Boat, offset: (i32, i32)) -> Result<(), String>;
+    fn render_boat_at(
+        &mut self,
+        boat: &Boat,
+        offset: (i32, i32),
+        tile_size: i32,
+    ) -> Result<(), String>;
+
+    // the letters of the columns above the board and the numbers of the rows on its left
+    fn render_labels(
+        &mut self,
+        offset: (i32, i32),
+        nb: usize,
+        tile_size: i32,
+    ) -> Result<(), String>;
 }
 
 impl GameRenderer for Canvas<Window> {
@@ -47,44 +75,55 @@ impl GameRenderer for Canvas<Window> {
         self.set_draw_color(WHITE);
         self.fill_rect(None).unwrap();
 
-        self.render_game(game!(game_type))
+        match game_type {
+            // the shot board of a party is the one of the targeted player
+            GameType::Party { game, party, .. } => {
+                self.render_terrain(game, (BOARD_X, OFFSET_Y), SIZE, true)?;
+                self.render_board_boat(&game.player, &game.terrain, (BOARD_X, OFFSET_Y), SIZE)?;
+                self.render_labels((BOARD_X, OFFSET_Y), game.terrain.len(), SIZE)?;
+                self.render_shot_board(&party.boards[party.target as usize])
+            }
+            _ => self.render_game(game!(game_type)),
+        }
     }
 
     fn render_grid(
         &mut self,
         offset: (i32, i32),
-        board_size: (i32, i32),
+        terrain: &[Vec<Terrain>],
         tile_size: i32,
     ) -> Result<(), String> {
+        // neighbouring cells share their borders, all drawn with a single call
+        let rects: Vec<Rect> = terrain
+            .iter()
+            .enumerate()
+            .flat_map(|(x, column)| {
+                column
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, cell)| **cell != Terrain::Outside)
+                    .map(move |(y, _)| {
+                        Rect::new(
+                            offset.0 + x as i32 * tile_size,
+                            offset.1 + y as i32 * tile_size,
+                            tile_size as u32 + 1,
+                            tile_size as u32 + 1,
+                        )
+                    })
+            })
+            .collect();
         self.set_draw_color(BLACK);
-
-        let width = board_size.0 * tile_size;
-        let height = board_size.1 * tile_size;
-        for i in 0..board_size.0 + 1 {
-            self.draw_line(
-                (offset.0 + i * tile_size, offset.1),
-                (offset.0 + i * tile_size, offset.1 + height),
-            )?;
-        }
-
-        for i in 0..board_size.1 + 1 {
-            self.draw_line(
-                (offset.0, offset.1 + i * tile_size),
-                (offset.0 + width, offset.1 + i * tile_size),
-            )?;
-        }
-
-        Ok(())
+        self.draw_rects(&rects)
     }
 
     fn render_board_boat(
         &mut self,
         player: &Player,
+        terrain: &[Vec<Terrain>],
         offset: (i32, i32),
-        board_size: (i32, i32),
         tile_size: i32,
     ) -> Result<(), String> {
-        self.render_grid(offset, board_size, tile_size)?;
+        self.render_grid(offset, terrain, tile_size)?;
         self.set_draw_color(BLACK);
         for boat in &player.boats {
             self.render_boat_at(
@@ -93,45 +132,150 @@ impl GameRenderer for Canvas<Window> {
                     offset.0 + tile_size / 2 + boat.position.0 as i32 * tile_size,
                     offset.1 + tile_size / 2 + boat.position.1 as i32 * tile_size,
                 ),
+                tile_size,
             )?;
         }
 
         Ok(())
     }
 
+    fn render_terrain(
+        &mut self,
+        game: &Game,
+        offset: (i32, i32),
+        tile_size: i32,
+        mines: bool,
+    ) -> Result<(), String> {
+        for (x, column) in game.terrain.iter().enumerate() {
+            for (y, terrain) in column.iter().enumerate() {
+                let (x, y) = (
+                    offset.0 + x as i32 * tile_size,
+                    offset.1 + y as i32 * tile_size,
+                );
+                if *terrain == Terrain::Water || *terrain == Terrain::Mine {
+                    self.render_water(x, y, tile_size)?;
+                }
+                match terrain {
+                    Terrain::Island => {
+                        self.set_draw_color(theme().island);
+                        self.fill_rect(Rect::new(x, y, tile_size as u32, tile_size as u32))?;
+                    }
+                    Terrain::Mine if mines => {
+                        self.set_draw_color(theme().mine);
+                        self.fill_rect(Rect::new(
+                            x + tile_size / 3,
+                            y + tile_size / 3,
+                            (tile_size / 3) as u32,
+                            (tile_size / 3) as u32,
+                        ))?;
+                    }
+                    _ => (),
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn render_shot_board(&mut self, game: &Game) -> Result<(), String> {
-        self.render_grid((OFFSET_X, 0), (NB, NB), SIZE)?;
+        // the islands are the same on both boards
+        self.render_terrain(game, (OFFSET_X, OFFSET_Y), SIZE, false)?;
+        self.render_grid((OFFSET_X, OFFSET_Y), &game.terrain, SIZE)?;
+        self.render_labels((OFFSET_X, OFFSET_Y), game.terrain.len(), SIZE)?;
         for i in 0..game.shot_boats.len() {
             for j in 0..game.shot_boats[i].len() {
-                if let Some(b) = game.shot_boats[i][j] {
-                    fill_circle(
-                        self,
-                        if b { WOUNDED_BOAT } else { HEALTHY_BOAT },
-                        OFFSET_X + SIZE / 2 + i as i32 * SIZE,
-                        SIZE / 2 + j as i32 * SIZE,
-                        SIZE / 3,
-                    )?
+                let (x, y) = (
+                    OFFSET_X + SIZE / 2 + i as i32 * SIZE,
+                    OFFSET_Y + SIZE / 2 + j as i32 * SIZE,
+                );
+                // a crossed circle for a hit, a dot for a miss and a ring for a revealed cell
+                match game.shot_boats[i][j] {
+                    Some(true) => {
+                        fill_circle(self, theme().wounded_boat, x, y, SIZE / 3)?;
+                        draw_cross(self, WHITE, x, y, SIZE / 5)?;
+                    }
+                    Some(false) => fill_circle(self, theme().miss, x, y, SIZE / 8)?,
+                    None if game.revealed[i][j] => {
+                        draw_circle(self, theme().revealed, x, y, SIZE / 4)?;
+                        draw_circle(self, theme().revealed, x, y, SIZE / 4 - 1)?;
+                    }
+                    None => (),
                 }
             }
         }
         Ok(())
     }
 
-    fn render_boat_at(&mut self, boat: &Boat, offset: (i32, i32)) -> Result<(), String> {
-        let dx = boat.direction.dx() as i32;
-        let dy = boat.direction.dy() as i32;
-        let mut len = 0;
-
-        for l in &boat.detailed_life {
-            fill_circle(
-                self,
-                if *l { HEALTHY_BOAT } else { WOUNDED_BOAT },
-                offset.0 + len * dx,
-                offset.1 + len * dy,
-                SIZE / 3,
-            )?;
+    fn render_boat_at(
+        &mut self,
+        boat: &Boat,
+        offset: (i32, i32),
+        tile_size: i32,
+    ) -> Result<(), String> {
+        let len = boat.max_life();
+        let hull = boat.hull.offsets(len);
+        // the last segment of the first row is the bow
+        let bow = hull
+            .iter()
+            .filter(|o| o.1 == 0)
+            .map(|o| o.0)
+            .max()
+            .unwrap_or(0);
+        let segments = boat.offsets(boat.hull).into_iter().zip(hull.iter());
+        for (l, ((dx, dy), &(a, b))) in boat.detailed_life.iter().zip(segments) {
+            let (x, y) = (
+                offset.0 + dx as i32 * tile_size,
+                offset.1 + dy as i32 * tile_size,
+            );
+            // the segments out of the first row look like the middle of the boat
+            let column = if b != 0 {
+                1
+            } else if a == bow {
+                len - 1
+            } else {
+                a as u8
+            };
+            if self.render_segment(boat.class, column, boat.direction, (x, y), tile_size, !*l)? {
+                continue;
+            }
+            if *l {
+                fill_circle(self, theme().healthy_boat, x, y, tile_size / 3)?;
+            } else {
+                // the wounded segments are crossed
+                fill_circle(self, theme().wounded_boat, x, y, tile_size / 3)?;
+                draw_cross(self, WHITE, x, y, tile_size / 5)?;
+            }
+        }
+        Ok(())
+    }
 
-            len += SIZE;
+    fn render_labels(
+        &mut self,
+        offset: (i32, i32),
+        nb: usize,
+        tile_size: i32,
+    ) -> Result<(), String> {
+        for i in 0..nb {
+            let i = i as i32;
+            self.render_text(
+                &((b'A' + i as u8) as char).to_string(),
+                TEXT_COLOR,
+                Rect::new(
+                    offset.0 + i * tile_size,
+                    offset.1 - LABEL_SIZE,
+                    tile_size as u32,
+                    LABEL_SIZE as u32,
+                ),
+            )?;
+            self.render_text(
+                &(i + 1).to_string(),
+                TEXT_COLOR,
+                Rect::new(
+                    offset.0 - LABEL_SIZE,
+                    offset.1 + i * tile_size,
+                    LABEL_SIZE as u32,
+                    tile_size as u32,
+                ),
+            )?;
         }
         Ok(())
     }
@@ -0,0 +1,80 @@
+// the panels are laid out for a window of WIDTH x HEIGHT, they are drawn scaled to fit the
+// actual window, centred and keeping their proportions
+use crate::view::{HEIGHT, WIDTH};
+use sdl2::{event::Event, rect::Rect, render::Canvas, video::Window};
+
+const MIN_SCALE: f32 = 0.25;
+const SCREEN_USAGE: f32 = 0.9; // the part of the screen the window can take when it opens
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Layout {
+    scale: f32,         // pixels of the window for a unit of the panels
+    offset: (i32, i32), // in pixels, the margins on the sides of the panels
+    dpi: f32,           // pixels for a unit of the window coordinates, above 1 on high DPI screens
+}
+
+impl Layout {
+    pub fn new(window: &Window) -> Self {
+        let (w, _) = window.size();
+        let (pixels_w, pixels_h) = window.drawable_size();
+        let scale = (pixels_w as f32 / WIDTH as f32)
+            .min(pixels_h as f32 / HEIGHT as f32)
+            .max(MIN_SCALE);
+        Layout {
+            scale,
+            offset: (
+                (pixels_w as i32 - (WIDTH as f32 * scale) as i32) / 2,
+                (pixels_h as i32 - (HEIGHT as f32 * scale) as i32) / 2,
+            ),
+            dpi: pixels_w as f32 / w.max(1) as f32,
+        }
+    }
+
+    // the size of the window when it opens, the panels are shrunk if the screen is too small
+    pub fn window_size(screen: (i32, i32)) -> (u32, u32) {
+        let scale = (SCREEN_USAGE * screen.0 as f32 / WIDTH as f32)
+            .min(SCREEN_USAGE * screen.1 as f32 / HEIGHT as f32)
+            .min(1.)
+            .max(MIN_SCALE);
+        (
+            (WIDTH as f32 * scale) as u32,
+            (HEIGHT as f32 * scale) as u32,
+        )
+    }
+
+    // from the coordinates of the window to the ones of the panels
+    pub fn to_panel(&self, x: i32, y: i32) -> (i32, i32) {
+        (
+            ((x as f32 * self.dpi - self.offset.0 as f32) / self.scale) as i32,
+            ((y as f32 * self.dpi - self.offset.1 as f32) / self.scale) as i32,
+        )
+    }
+
+    // the same event with the position of the mouse in the coordinates of the panels
+    pub fn convert_event(&self, mut event: Event) -> Event {
+        match &mut event {
+            Event::MouseButtonDown { x, y, .. }
+            | Event::MouseButtonUp { x, y, .. }
+            | Event::MouseMotion { x, y, .. } => {
+                let (panel_x, panel_y) = self.to_panel(*x, *y);
+                *x = panel_x;
+                *y = panel_y;
+            }
+            _ => (),
+        }
+        event
+    }
+
+    // the next drawings on the canvas are scaled and moved to the area of the panels
+    pub fn apply(&self, canvas: &mut Canvas<Window>) -> Result<(), String> {
+        canvas.set_scale(self.scale, self.scale)?;
+        // the viewport is given in the scaled coordinates
+        canvas.set_viewport(Rect::new(
+            (self.offset.0 as f32 / self.scale) as i32,
+            (self.offset.1 as f32 / self.scale) as i32,
+            WIDTH as u32,
+            HEIGHT as u32,
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,223 @@
+// party lobby panel, where the players gather before placing their fleet
+use crate::model::{
+    game::Game,
+    party::{MAX_PLAYERS, MIN_PLAYERS, TEAM_PLAYERS},
+    rules::Rules,
+    terrain::Map,
+};
+use crate::network::{create_party_socket, find_party, start_party, wait_client, wait_party_start};
+use crate::utils::*;
+use crate::view::{
+    buttons::Button,
+    creation::{CreationPanel, Opponent},
+    panel::{Panel, TEXT_COLOR},
+};
+use crate::NB;
+use sdl2::{
+    event::{Event, Event::MouseButtonUp},
+    mouse::MouseButton,
+    render::Canvas,
+    video::Window,
+};
+use std::{mem::replace, net::TcpListener, net::TcpStream};
+
+pub struct LobbyPanel {
+    buttons: Vec<Button>,
+    start_button: Button,
+    teams_button: Button,
+    player_buttons: Vec<Button>,   // one for each seat of the party
+    listener: Option<TcpListener>, // only for the host
+    sockets: Vec<TcpStream>,       // the clients for the host, the host for a client
+    rules: Rules,                  // the rules of the host
+    map: Map,                      // the map of the host
+    teams: bool,                   // two teams of two players instead of a free-for-all
+}
+
+impl LobbyPanel {
+    pub fn host(rules: Rules, map: Map) -> Result<Self, String> {
+        // the boats can't be moved nor use their ability in a party, and there is no mine
+        if !rules.party_compatible() {
+            return Err(
+                "Disable the moving boats, the abilities and the obstacles to host a party"
+                    .to_string(),
+            );
+        }
+        Game::fits(&map, rules)?;
+        Ok(LobbyPanel::new(
+            Some(create_party_socket()?),
+            vec![],
+            rules,
+            map,
+        ))
+    }
+
+    pub fn join(address: &str) -> Result<Self, String> {
+        Ok(LobbyPanel::new(
+            None,
+            vec![find_party(address)?],
+            Rules::default(),
+            Map::new(NB as usize, NB as usize),
+        ))
+    }
+
+    fn new(listener: Option<TcpListener>, sockets: Vec<TcpStream>, rules: Rules, map: Map) -> Self {
+        println!("Creation of the lobby panel");
+        let mut panel = LobbyPanel {
+            buttons: vec![],
+            start_button: Button::new(
+                0,
+                0,
+                100,
+                100,
+                YELLOW,
+                "Start".to_string(),
+                TEXT_COLOR,
+                Box::new(|| None),
+            ),
+            teams_button: Button::new(
+                0,
+                100,
+                100,
+                100,
+                GREY,
+                "Teams".to_string(),
+                TEXT_COLOR,
+                Box::new(|| None),
+            ),
+            player_buttons: (0..MAX_PLAYERS as i32)
+                .map(|i| {
+                    Button::new(
+                        150 + i * 110,
+                        0,
+                        100,
+                        100,
+                        GREY,
+                        format!("Player {}", i + 1),
+                        TEXT_COLOR,
+                        Box::new(|| None),
+                    )
+                })
+                .collect(),
+            listener,
+            sockets,
+            rules,
+            map,
+            teams: false,
+        };
+        panel.refresh_players();
+        panel
+    }
+
+    // colours the seats taken, by team if any
+    fn refresh_players(&mut self) {
+        // the host is always the first player, a client doesn't know the other ones
+        let joined = if self.is_host() {
+            self.sockets.len() + 1
+        } else {
+            1
+        };
+        let teams = self.teams;
+        for (i, button) in self.player_buttons.iter_mut().enumerate() {
+            button.set_background(match i {
+                i if i >= joined => GREY,
+                i if teams && i % 2 == 0 => CYAN,
+                _ if teams => MAGENTA,
+                _ => GREEN,
+            });
+        }
+        self.teams_button
+            .set_background(if teams { GREEN } else { GREY });
+    }
+
+    fn is_host(&self) -> bool {
+        self.listener.is_some()
+    }
+
+    fn can_start(&self) -> bool {
+        let count = self.sockets.len() + 1;
+        self.is_host()
+            && if self.teams {
+                count == TEAM_PLAYERS
+            } else {
+                count >= MIN_PLAYERS
+            }
+    }
+}
+
+impl Panel for LobbyPanel {
+    fn title(&self) -> &str {
+        "Party lobby"
+    }
+
+    fn button_vec_mut(&mut self) -> &mut Vec<Button> {
+        &mut self.buttons
+    }
+
+    fn button_vec(&self) -> &Vec<Button> {
+        &self.buttons
+    }
+
+    fn manage_event(&mut self, event: Event) -> Result<Option<Box<Panel>>, String> {
+        if let MouseButtonUp {
+            mouse_btn: MouseButton::Left,
+            x,
+            y,
+            ..
+        } = event
+        {
+            if self.can_start() && self.start_button.contains_point((x, y)) {
+                let sockets = replace(&mut self.sockets, vec![]);
+                let party = start_party(sockets, self.rules, self.teams, &self.map)?;
+                let (rules, map) = (party.boards[0].rules, party.map.clone());
+                return Ok(Some(Box::new(CreationPanel::new(
+                    Opponent::Party(Some(party)),
+                    rules,
+                    map,
+                ))));
+            } else if self.is_host() && self.teams_button.contains_point((x, y)) {
+                self.teams = !self.teams;
+                self.refresh_players();
+            }
+        }
+        Ok(None)
+    }
+
+    fn render(&self, canvas: &mut Canvas<Window>, _mouse: (i32, i32)) -> Result<(), String> {
+        canvas.set_draw_color(WHITE);
+        canvas.fill_rect(None)?;
+        for button in &self.buttons {
+            button.render(canvas)?;
+        }
+        for button in &self.player_buttons {
+            button.render(canvas)?;
+        }
+        if self.is_host() {
+            self.teams_button.render(canvas)?;
+        }
+        if self.can_start() {
+            self.start_button.render(canvas)?;
+        }
+        Ok(())
+    }
+
+    fn do_loop(&mut self) -> Result<Option<Box<Panel>>, String> {
+        if let Some(listener) = &self.listener {
+            if let Some(socket) = wait_client(listener) {
+                if self.sockets.len() + 1 < MAX_PLAYERS {
+                    self.sockets.push(socket);
+                    self.refresh_players();
+                } else {
+                    println!("The party is full");
+                }
+            }
+        } else if let Some(party) = wait_party_start(&self.sockets[0])? {
+            let (rules, map) = (party.boards[0].rules, party.map.clone());
+            return Ok(Some(Box::new(CreationPanel::new(
+                Opponent::Party(Some(party)),
+                rules,
+                map,
+            ))));
+        }
+        Ok(None)
+    }
+}
@@ -0,0 +1,81 @@
+// button cycling through the available maps, with a preview of the selected one
+use crate::model::terrain::{Map, Terrain};
+use crate::save::list_maps;
+use crate::utils::*;
+use crate::view::{buttons::Button, panel::TEXT_COLOR};
+use sdl2::{rect::Rect, render::Canvas, video::Window};
+
+const BUTTON_SIZE: i32 = 50;
+const PREVIEW_SIZE: i32 = 4; // the size of a cell in the preview
+
+pub struct MapSelector {
+    maps: Vec<Map>,
+    selected: usize,
+    button: Button,
+    position: (i32, i32), // the one of the button, the preview is on its right
+}
+
+impl MapSelector {
+    // the selected map is the given one, even if it isn't in the maps directory
+    pub fn new(x: i32, y: i32, map: Map) -> Self {
+        let mut maps = list_maps();
+        let selected = match maps.iter().position(|m| *m == map) {
+            Some(i) => i,
+            None => {
+                maps.push(map);
+                maps.len() - 1
+            }
+        };
+        MapSelector {
+            button: Button::new(
+                x,
+                y,
+                BUTTON_SIZE,
+                BUTTON_SIZE,
+                BLUE,
+                maps[selected].name.clone(),
+                TEXT_COLOR,
+                Box::new(|| None),
+            ),
+            maps,
+            selected,
+            position: (x, y),
+        }
+    }
+
+    pub fn map(&self) -> &Map {
+        &self.maps[self.selected]
+    }
+
+    // returns true if the click selected the next map
+    pub fn click(&mut self, x: i32, y: i32) -> bool {
+        if !self.button.contains_point((x, y)) {
+            return false;
+        }
+        self.selected = (self.selected + 1) % self.maps.len();
+        self.button.set_text(self.map().name.clone());
+        println!("map : {}", self.map().name);
+        true
+    }
+
+    pub fn render(&self, canvas: &mut Canvas<Window>) -> Result<(), String> {
+        self.button.render(canvas)?;
+        let (x, y) = self.position;
+        for (i, column) in self.map().terrain.iter().enumerate() {
+            for (j, terrain) in column.iter().enumerate() {
+                canvas.set_draw_color(match terrain {
+                    Terrain::Outside => continue,
+                    Terrain::Island => GREY,
+                    _ => CYAN,
+                });
+                canvas.fill_rect(Rect::new(
+                    x + BUTTON_SIZE + 10 + i as i32 * PREVIEW_SIZE,
+                    y + j as i32 * PREVIEW_SIZE,
+                    PREVIEW_SIZE as u32,
+                    PREVIEW_SIZE as u32,
+                ))?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,12 +1,16 @@
 // menu panel
 use crate::{
+    model::{game::GameType, replay::Replay, rules::Rules, terrain::Map},
+    save::{REPLAY_FILE, SAVE_FILE},
     utils::*,
     view::{
         buttons::Button,
         connection::ConnectPanel,
-        creation::CreationPanel,
+        creation::{CreationPanel, Opponent},
+        game::GamePanel,
         panel::{Panel, QUIT_COLOR, TEXT_COLOR},
-        HEIGHT, WIDTH,
+        replay::ReplayPanel,
+        HEIGHT, NB, WIDTH,
     },
 };
 
@@ -16,9 +20,9 @@ pub struct MenuPanel {
 
 impl MenuPanel {
     pub fn new() -> Self {
-        let n = 3;
+        let n = 6;
         let width = 200;
-        let height = 100;
+        let height = 80;
         let v_space = (HEIGHT - n * height) / (n + 1);
         let h_space = (WIDTH - width) / 2;
 
@@ -42,13 +46,67 @@ impl MenuPanel {
                     BLUE,
                     "AI game".to_string(),
                     TEXT_COLOR,
-                    Box::new(|| Some(Box::new(CreationPanel::new(None)))),
+                    Box::new(|| {
+                        Some(Box::new(CreationPanel::new(
+                            Opponent::Ai,
+                            Rules::default(),
+                            Map::new(NB as usize, NB as usize),
+                        )))
+                    }),
                 ),
                 Button::new(
                     h_space,
                     3 * v_space + 2 * height,
                     width,
                     height,
+                    MAGENTA,
+                    "Hot seat game".to_string(),
+                    TEXT_COLOR,
+                    Box::new(|| {
+                        Some(Box::new(CreationPanel::new(
+                            Opponent::Local(None),
+                            Rules::default(),
+                            Map::new(NB as usize, NB as usize),
+                        )))
+                    }),
+                ),
+                Button::new(
+                    h_space,
+                    4 * v_space + 3 * height,
+                    width,
+                    height,
+                    YELLOW,
+                    "Load game".to_string(),
+                    TEXT_COLOR,
+                    Box::new(|| match GameType::load(SAVE_FILE) {
+                        Ok(game) => Some(Box::new(GamePanel::new(game))),
+                        Err(e) => {
+                            println!("{}", e);
+                            None
+                        }
+                    }),
+                ),
+                Button::new(
+                    h_space,
+                    5 * v_space + 4 * height,
+                    width,
+                    height,
+                    CYAN,
+                    "Replay last game".to_string(),
+                    TEXT_COLOR,
+                    Box::new(|| match Replay::load(REPLAY_FILE) {
+                        Ok(replay) => Some(Box::new(ReplayPanel::new(replay))),
+                        Err(e) => {
+                            println!("{}", e);
+                            None
+                        }
+                    }),
+                ),
+                Button::new(
+                    h_space,
+                    6 * v_space + 5 * height,
+                    width,
+                    height,
                     QUIT_COLOR,
                     "Quit".to_string(),
                     TEXT_COLOR,
@@ -60,6 +118,10 @@ impl MenuPanel {
 }
 
 impl Panel for MenuPanel {
+    fn title(&self) -> &str {
+        "Menu"
+    }
+
     fn button_vec_mut(&mut self) -> &mut Vec<Button> {
         &mut self.buttons
     }
@@ -1,66 +1,108 @@
+mod animations;
 mod buttons;
 mod connection;
 mod creation;
 mod end_game;
 mod game;
 mod game_renderer;
+mod layout;
+mod lobby;
+mod maps;
 mod menu;
 mod panel;
+mod replay;
+mod rules;
+mod sound;
+mod sprites;
+mod status;
+mod text;
+mod theme;
 
 use crate::{
+    utils::BLACK,
     view::{
-        game::{BOARD_WIDTH, DELTA},
+        game::{BOARD_WIDTH, OFFSET_X, OFFSET_Y},
+        layout::Layout,
         menu::MenuPanel,
         panel::Panel,
+        status::STATUS_HEIGHT,
     },
     NB,
 };
 use sdl2::{
-    event::{Event, EventType},
+    event::{Event, EventType, WindowEvent},
     keyboard::Keycode,
     mouse::MouseState,
 };
 use std::{thread, time};
 
-pub const HEIGHT: i32 = BOARD_WIDTH;
-pub const WIDTH: i32 = 2 * BOARD_WIDTH + DELTA;
+pub const HEIGHT: i32 = OFFSET_Y + BOARD_WIDTH + STATUS_HEIGHT;
+pub const WIDTH: i32 = OFFSET_X + BOARD_WIDTH;
+pub const FRAME_MS: u64 = 30; // the duration of a turn of the loop
 
 pub fn run() -> Result<(), String> {
     let sdl_context = sdl2::init().map_err(|x| x.to_string())?;
     let video_subsystem = sdl_context.video().map_err(|x| x.to_string())?;
     //    let event_subsystem = sdl_context.event().unwrap();
-    //    let audio_subsystem = sdl_context.audio().unwrap();
     //    let timer_subsystem = sdl_context.timer().unwrap();
 
+    let screen = video_subsystem.display_bounds(0)?;
+    let (width, height) = Layout::window_size((screen.width() as i32, screen.height() as i32));
     let window = video_subsystem
-        .window("Rust Battleship", WIDTH as u32, HEIGHT as u32)
+        .window("Rust Battleship", width, height)
         .position_centered()
+        .resizable()
+        .allow_highdpi()
         .build()
         .map_err(|x| x.to_string())?;
 
+    sound::init(&sdl_context);
+
     let mut canvas = window.into_canvas().build().map_err(|x| x.to_string())?;
     let mut event_pump = sdl_context.event_pump()?;
+    let mut layout = Layout::new(canvas.window());
 
-    event_pump.disable_event(EventType::MouseMotion);
+    event_pump.enable_event(EventType::MouseMotion);
     event_pump.enable_event(EventType::AppTerminating);
     event_pump.enable_event(EventType::MouseButtonUp);
     event_pump.enable_event(EventType::MouseButtonDown);
     event_pump.enable_event(EventType::KeyUp);
     event_pump.enable_event(EventType::KeyDown);
     event_pump.enable_event(EventType::Quit);
+    event_pump.enable_event(EventType::Window);
 
     let mut panel: Box<Panel> = Box::new(MenuPanel::new());
+    let mut title = String::new();
+    let mut redraw = true;
 
     loop {
-        canvas.clear();
-        panel.render(&mut canvas, MouseState::new(&event_pump))?;
-        canvas.present();
+        if title != panel.title() {
+            title = panel.title().to_string();
+            canvas
+                .window_mut()
+                .set_title(&format!("Rust Battleship - {}", title))
+                .map_err(|x| x.to_string())?;
+        }
+        // drawn again only after an event or a change of the panel
+        if redraw || panel.changed() {
+            // the margins around the panels
+            canvas.set_viewport(None);
+            canvas.set_draw_color(BLACK);
+            canvas.clear();
+            layout.apply(&mut canvas)?;
+            let mouse = MouseState::new(&event_pump);
+            panel.render(&mut canvas, layout.to_panel(mouse.x(), mouse.y()))?;
+            canvas.present();
+            redraw = false;
+        }
         if let Some(newpanel) = panel.do_loop()? {
             panel = newpanel;
+            redraw = true;
             continue;
         }
 
         for event in event_pump.poll_iter() {
+            redraw = true;
             match event {
                 Event::Quit { .. }
                 | Event::AppTerminating { .. }
@@ -75,8 +117,44 @@ pub fn run() -> Result<(), String> {
                     panel = Box::new(MenuPanel::new());
                     continue;
                 }
+                Event::KeyUp {
+                    keycode: Some(Keycode::T),
+                    ..
+                } => theme::next_theme(),
+                Event::KeyUp {
+                    keycode: Some(Keycode::G),
+                    ..
+                } => sprites::toggle_sprites(),
+                Event::KeyUp {
+                    keycode: Some(Keycode::M),
+                    ..
+                } => sound::toggle_mute(),
+                Event::KeyUp {
+                    keycode: Some(Keycode::N),
+                    ..
+                } => sound::toggle_music(),
+                Event::KeyUp {
+                    keycode: Some(Keycode::Equals),
+                    ..
+                }
+                | Event::KeyUp {
+                    keycode: Some(Keycode::KpPlus),
+                    ..
+                } => sound::change_volume(true),
+                Event::KeyUp {
+                    keycode: Some(Keycode::Minus),
+                    ..
+                }
+                | Event::KeyUp {
+                    keycode: Some(Keycode::KpMinus),
+                    ..
+                } => sound::change_volume(false),
+                Event::Window {
+                    win_event: WindowEvent::SizeChanged(..),
+                    ..
+                } => layout = Layout::new(canvas.window()),
                 e => {
-                    if let Some(newpanel) = panel.manage_event(e)? {
+                    if let Some(newpanel) = panel.manage_event(layout.convert_event(e))? {
                         panel = newpanel;
                         continue;
                     }
@@ -84,6 +162,6 @@ pub fn run() -> Result<(), String> {
             };
         }
 
-        thread::sleep(time::Duration::from_millis(30));
+        thread::sleep(time::Duration::from_millis(FRAME_MS));
     }
 }
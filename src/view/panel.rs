@@ -2,7 +2,7 @@ use crate::utils::*;
 use crate::view::buttons::Button;
 use sdl2::{
     event::{Event, Event::MouseButtonUp},
-    mouse::{MouseButton, MouseState},
+    mouse::MouseButton,
     pixels::Color,
     render::Canvas,
     video::Window,
@@ -12,10 +12,14 @@ pub const QUIT_COLOR: Color = RED;
 pub const TEXT_COLOR: Color = BLACK;
 
 pub trait Panel {
+    // shown in the title of the window
+    fn title(&self) -> &str;
+
     fn button_vec_mut(&mut self) -> &mut Vec<Button>;
     fn button_vec(&self) -> &Vec<Button>;
 
-    fn render(&self, canvas: &mut Canvas<Window>, _mouse_state: MouseState) -> Result<(), String> {
+    // the position of the mouse is given in the coordinates of the panel
+    fn render(&self, canvas: &mut Canvas<Window>, _mouse: (i32, i32)) -> Result<(), String> {
         canvas.set_draw_color(WHITE);
         canvas.fill_rect(None)?;
         for button in self.button_vec() {
@@ -24,6 +28,12 @@ pub trait Panel {
         Ok(())
     }
 
+    // whether the panel must be drawn again without any event, the panels changing on their own
+    // are always drawn by default
+    fn changed(&self) -> bool {
+        true
+    }
+
     // the Ok part is true to 'continue' the main loop (go back to the beginning) and false otherwise
     fn manage_event(&mut self, event: Event) -> Result<Option<Box<Panel>>, String> {
         if let MouseButtonUp {
@@ -0,0 +1,208 @@
+// replay panel, to step through a recorded game
+use crate::model::{
+    boat::Boat,
+    game::Game,
+    player::Player,
+    replay::{Move, Replay},
+    rules::Rules,
+};
+use crate::utils::*;
+use crate::view::{
+    buttons::Button,
+    game::{DELTA, GAP_X},
+    game_renderer::GameRenderer,
+    panel::{Panel, TEXT_COLOR},
+};
+use crate::NB;
+use sdl2::{
+    event::{
+        Event,
+        Event::{KeyUp, MouseButtonUp},
+    },
+    keyboard::Keycode,
+    mouse::MouseButton,
+    render::Canvas,
+    video::Window,
+};
+
+pub struct ReplayPanel {
+    buttons: Vec<Button>,
+    previous_button: Button,
+    next_button: Button,
+    replay: Replay,
+    step: usize,       // number of records already played
+    perspective: bool, // true to see the game as the main player, false as the opponent
+    game: Game,        // the game as seen from the perspective at the current step
+}
+
+impl ReplayPanel {
+    pub fn new(replay: Replay) -> Self {
+        let mut panel = ReplayPanel {
+            buttons: vec![],
+            previous_button: Button::new(
+                GAP_X,
+                0,
+                DELTA,
+                DELTA,
+                CYAN,
+                "Previous".to_string(),
+                TEXT_COLOR,
+                Box::new(|| None),
+            ),
+            next_button: Button::new(
+                GAP_X,
+                DELTA,
+                DELTA,
+                DELTA,
+                GREEN,
+                "Next".to_string(),
+                TEXT_COLOR,
+                Box::new(|| None),
+            ),
+            replay,
+            step: 0,
+            perspective: true,
+            game: Game::new(
+                NB as usize,
+                NB as usize,
+                Player::new(vec![]),
+                Rules::default(),
+            )
+            .unwrap(),
+        };
+        panel.go_to(0);
+        panel
+    }
+
+    // replays the records from the beginning up to the given step
+    fn go_to(&mut self, step: usize) {
+        self.step = step.min(self.replay.records.len());
+
+        let mut boats = vec![];
+        for record in &self.replay.records[..self.step] {
+            if let Move::Place {
+                player,
+                class,
+                position,
+                direction,
+            } = record.action
+            {
+                if player == self.perspective {
+                    boats.push(Boat::new(class, position, direction));
+                }
+            }
+        }
+
+        self.game = match Game::with_map(&self.replay.map, Player::new(boats), self.replay.rules) {
+            Ok(game) => game,
+            Err(e) => {
+                println!("Invalid replay : {}", e);
+                Game::with_map(&self.replay.map, Player::new(vec![]), self.replay.rules).unwrap()
+            }
+        };
+
+        for record in &self.replay.records[..self.step] {
+            match record.action {
+                Move::Attack {
+                    player,
+                    position,
+                    shot,
+                }
+                | Move::Mine {
+                    player,
+                    position,
+                    shot,
+                } => {
+                    if player == self.perspective {
+                        self.game.confirm_attack(position, shot);
+                    } else {
+                        self.game.opponent_attack(position);
+                    }
+                }
+                Move::Shift {
+                    player,
+                    class,
+                    movement,
+                } => {
+                    if player == self.perspective {
+                        if let Err(e) = self.game.move_boat(class, movement) {
+                            println!("Invalid replay : {}", e);
+                        }
+                    } else {
+                        self.game.forget_misses();
+                    }
+                }
+                Move::Reveal {
+                    player,
+                    position,
+                    occupied,
+                } => {
+                    if player == self.perspective {
+                        self.game.reveal(position, occupied);
+                    }
+                }
+                Move::Place { .. } | Move::Ability { .. } => (),
+            }
+        }
+
+        if self.step > 0 {
+            let record = &self.replay.records[self.step - 1];
+            println!("replay {:?} : {}", record.time, record.action);
+        }
+    }
+}
+
+impl Panel for ReplayPanel {
+    fn title(&self) -> &str {
+        "Replay"
+    }
+
+    fn button_vec_mut(&mut self) -> &mut Vec<Button> {
+        &mut self.buttons
+    }
+
+    fn button_vec(&self) -> &Vec<Button> {
+        &self.buttons
+    }
+
+    fn manage_event(&mut self, event: Event) -> Result<Option<Box<Panel>>, String> {
+        match event {
+            MouseButtonUp {
+                mouse_btn: MouseButton::Left,
+                x,
+                y,
+                ..
+            } => {
+                if self.previous_button.contains_point((x, y)) {
+                    self.go_to(self.step.saturating_sub(1));
+                } else if self.next_button.contains_point((x, y)) {
+                    self.go_to(self.step + 1);
+                }
+            }
+            KeyUp {
+                keycode: Some(keycode),
+                ..
+            } => match keycode {
+                Keycode::Left => self.go_to(self.step.saturating_sub(1)),
+                Keycode::Right => self.go_to(self.step + 1),
+                Keycode::Home => self.go_to(0),
+                Keycode::End => self.go_to(self.replay.records.len()),
+                Keycode::Tab => {
+                    self.perspective = !self.perspective;
+                    self.go_to(self.step);
+                }
+                _ => (),
+            },
+            _ => (),
+        }
+        Ok(None)
+    }
+
+    fn render(&self, canvas: &mut Canvas<Window>, _mouse: (i32, i32)) -> Result<(), String> {
+        canvas.set_draw_color(WHITE);
+        canvas.fill_rect(None)?;
+        canvas.render_game(&self.game)?;
+        self.previous_button.render(canvas)?;
+        self.next_button.render(canvas)
+    }
+}
@@ -0,0 +1,78 @@
+// toggle buttons to choose the rules of a game
+use crate::model::rules::Rules;
+use crate::utils::*;
+use crate::view::{buttons::Button, panel::TEXT_COLOR};
+use sdl2::{render::Canvas, video::Window};
+
+const TOGGLE_SIZE: i32 = 50;
+const TOGGLE_SPACE: i32 = 60;
+const TOGGLES_PER_ROW: i32 = 3;
+
+// the name of each rule and how to access it
+const TOGGLES: [(&str, fn(&mut Rules) -> &mut bool); 8] = [
+    ("Salvo", |r| &mut r.salvo),
+    ("Shoot again", |r| &mut r.shoot_again),
+    ("No touching", |r| &mut r.no_touching),
+    ("Moving boats", |r| &mut r.moving_boats),
+    ("Abilities", |r| &mut r.abilities),
+    ("Obstacles", |r| &mut r.obstacles),
+    ("Diagonal boats", |r| &mut r.diagonal_boats),
+    ("Shaped hulls", |r| &mut r.shaped_hulls),
+];
+
+pub struct RulesSelector {
+    pub rules: Rules,
+    buttons: Vec<Button>,
+}
+
+impl RulesSelector {
+    pub fn new(x: i32, y: i32, rules: Rules) -> Self {
+        let mut selector = RulesSelector {
+            rules,
+            buttons: (0..TOGGLES.len() as i32)
+                .map(|i| {
+                    Button::new(
+                        x + (i % TOGGLES_PER_ROW) * TOGGLE_SPACE,
+                        y + (i / TOGGLES_PER_ROW) * TOGGLE_SPACE,
+                        TOGGLE_SIZE,
+                        TOGGLE_SIZE,
+                        RED,
+                        TOGGLES[i as usize].0.to_string(),
+                        TEXT_COLOR,
+                        Box::new(|| None),
+                    )
+                })
+                .collect(),
+        };
+        selector.update_buttons();
+        selector
+    }
+
+    // green for the enabled rules, red for the disabled ones
+    fn update_buttons(&mut self) {
+        for (button, (_, rule)) in self.buttons.iter_mut().zip(TOGGLES.iter()) {
+            button.set_background(if *rule(&mut self.rules) { GREEN } else { RED });
+        }
+    }
+
+    // returns true if the click toggled a rule
+    pub fn click(&mut self, x: i32, y: i32) -> bool {
+        for (button, (name, rule)) in self.buttons.iter().zip(TOGGLES.iter()) {
+            if button.contains_point((x, y)) {
+                let value = rule(&mut self.rules);
+                *value = !*value;
+                println!("rule {} : {}", name, value);
+                self.update_buttons();
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn render(&self, canvas: &mut Canvas<Window>) -> Result<(), String> {
+        for button in &self.buttons {
+            button.render(canvas)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,246 @@
+// sound effects and music, synthesised when the audio device is opened and mixed by its callback,
+// the game is silent if there is no audio device
+use rand::Rng;
+use sdl2::{
+    audio::{AudioCallback, AudioDevice, AudioSpecDesired},
+    Sdl,
+};
+use std::{cell::RefCell, env, f32::consts::PI, time::Duration};
+
+const FREQUENCY: i32 = 44100;
+// the initial volume between 0 and 1
+const VOLUME_VARIABLE: &str = "BATTLESHIP_VOLUME";
+const VOLUME_STEP: f32 = 0.1;
+const MUSIC_VOLUME: f32 = 0.2; // relative to the effects
+const MUSIC_NOTE: f32 = 0.25; // seconds
+                              // the notes of the music loop as semitones from A 220 Hz, the bass plays the first note of each bar
+const MUSIC: [i32; 32] = [
+    0, 3, 7, 3, 0, 3, 7, 10, -2, 2, 5, 2, -2, 2, 5, 9, -4, 0, 3, 0, -4, 0, 3, 7, -5, -1, 2, -1, -5,
+    2, 5, 7,
+];
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Effect {
+    Fire = 0,
+    Miss = 1,
+    Hit = 2,
+    Sunk = 3,
+    Victory = 4,
+    Defeat = 5,
+}
+
+const EFFECTS: [Effect; 6] = [
+    Effect::Fire,
+    Effect::Miss,
+    Effect::Hit,
+    Effect::Sunk,
+    Effect::Victory,
+    Effect::Defeat,
+];
+
+struct Mixer {
+    channels: usize,
+    sounds: Vec<Vec<f32>>,      // the samples of each effect
+    voices: Vec<(Effect, i64)>, // the effects being played and their position, negative before they start
+    music: Vec<f32>,
+    music_position: usize,
+    music_on: bool,
+    volume: f32,
+    muted: bool,
+}
+
+impl AudioCallback for Mixer {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [f32]) {
+        for frame in out.chunks_mut(self.channels) {
+            let mut sample = 0.;
+            for (effect, position) in &mut self.voices {
+                if *position >= 0 {
+                    sample += self.sounds[*effect as usize]
+                        .get(*position as usize)
+                        .unwrap_or(&0.);
+                }
+                *position += 1;
+            }
+            if self.music_on {
+                sample += MUSIC_VOLUME * self.music[self.music_position];
+                self.music_position = (self.music_position + 1) % self.music.len();
+            }
+            let sample = if self.muted {
+                0.
+            } else {
+                (sample * self.volume).max(-1.).min(1.)
+            };
+            for s in frame {
+                *s = sample;
+            }
+        }
+        let sounds = &self.sounds;
+        self.voices
+            .retain(|(effect, position)| *position < sounds[*effect as usize].len() as i64);
+    }
+}
+
+thread_local! {
+    static SOUND: RefCell<Option<AudioDevice<Mixer>>> = RefCell::new(None);
+}
+
+fn samples(duration: f32) -> usize {
+    (duration * FREQUENCY as f32) as usize
+}
+
+// the frequency of the note this number of semitones away from A 220 Hz
+fn note(semitones: i32) -> f32 {
+    220. * 2f32.powf(semitones as f32 / 12.)
+}
+
+// a wave going from a frequency to another, square or sine, fading out
+fn tone(from: f32, to: f32, duration: f32, square: bool) -> Vec<f32> {
+    let n = samples(duration);
+    let mut phase = 0.;
+    (0..n)
+        .map(|i| {
+            let t = i as f32 / n as f32;
+            phase += 2. * PI * (from + (to - from) * t) / FREQUENCY as f32;
+            let wave = if square {
+                phase.sin().signum() * 0.5
+            } else {
+                phase.sin()
+            };
+            wave * (1. - t)
+        })
+        .collect()
+}
+
+// white noise fading out, smoothed to sound lower
+fn noise(duration: f32, smoothing: f32) -> Vec<f32> {
+    let mut rng = rand::thread_rng();
+    let n = samples(duration);
+    let mut last = 0.;
+    (0..n)
+        .map(|i| {
+            last += (rng.gen_range(-1., 1.) - last) * (1. - smoothing);
+            last * (1. - i as f32 / n as f32)
+        })
+        .collect()
+}
+
+fn mix(sounds: &[Vec<f32>]) -> Vec<f32> {
+    let len = sounds.iter().map(|s| s.len()).max().unwrap_or(0);
+    (0..len)
+        .map(|i| sounds.iter().filter_map(|s| s.get(i)).sum())
+        .collect()
+}
+
+// the notes one after the other
+fn melody(notes: &[i32], duration: f32) -> Vec<f32> {
+    notes
+        .iter()
+        .flat_map(|n| tone(note(*n), note(*n), duration, true))
+        .collect()
+}
+
+fn synthesise(effect: Effect) -> Vec<f32> {
+    match effect {
+        Effect::Fire => mix(&[noise(0.15, 0.3), tone(800., 200., 0.3, true)]),
+        Effect::Miss => noise(0.5, 0.9),
+        Effect::Hit => mix(&[noise(0.4, 0.6), tone(90., 40., 0.4, false)]),
+        Effect::Sunk => mix(&[noise(1.2, 0.95), tone(70., 25., 1.2, false)]),
+        Effect::Victory => melody(&[3, 7, 10, 15, 15], 0.15),
+        Effect::Defeat => melody(&[3, -2, -6, -9, -9], 0.25),
+    }
+}
+
+fn synthesise_music() -> Vec<f32> {
+    let bass: Vec<f32> = MUSIC
+        .chunks(8)
+        .flat_map(|bar| tone(note(bar[0] - 24), note(bar[0] - 24), 8. * MUSIC_NOTE, false))
+        .collect();
+    mix(&[melody(&MUSIC, MUSIC_NOTE), bass])
+        .iter()
+        .map(|s| s * 0.5)
+        .collect()
+}
+
+// opens the audio device, prints why if it isn't available
+pub fn init(sdl_context: &Sdl) {
+    let volume = match env::var(VOLUME_VARIABLE) {
+        Ok(s) => s.parse::<f32>().unwrap_or_else(|_| {
+            println!(
+                "Invalid {} '{}', the full volume is used",
+                VOLUME_VARIABLE, s
+            );
+            1.
+        }),
+        Err(_) => 1.,
+    };
+    let device = sdl_context.audio().and_then(|audio| {
+        audio.open_playback(
+            None,
+            &AudioSpecDesired {
+                freq: Some(FREQUENCY),
+                channels: Some(1),
+                samples: None,
+            },
+            |spec| Mixer {
+                channels: spec.channels.max(1) as usize,
+                sounds: EFFECTS.iter().map(|e| synthesise(*e)).collect(),
+                voices: vec![],
+                music: synthesise_music(),
+                music_position: 0,
+                music_on: true,
+                volume: volume.max(0.).min(1.),
+                muted: false,
+            },
+        )
+    });
+    match device {
+        Ok(device) => {
+            device.resume();
+            SOUND.with(|sound| *sound.borrow_mut() = Some(device));
+        }
+        Err(e) => println!("No audio : {}", e),
+    }
+}
+
+fn with_mixer(f: impl FnOnce(&mut Mixer)) {
+    SOUND.with(|sound| {
+        if let Some(device) = sound.borrow_mut().as_mut() {
+            f(&mut device.lock());
+        }
+    })
+}
+
+pub fn play(effect: Effect) {
+    play_after(effect, Duration::from_millis(0));
+}
+
+pub fn play_after(effect: Effect, delay: Duration) {
+    let delay = delay.as_millis() as i64 * FREQUENCY as i64 / 1000;
+    with_mixer(|mixer| mixer.voices.push((effect, -delay)));
+}
+
+pub fn toggle_mute() {
+    with_mixer(|mixer| {
+        mixer.muted = !mixer.muted;
+        println!("sound {}", if mixer.muted { "muted" } else { "on" });
+    });
+}
+
+pub fn toggle_music() {
+    with_mixer(|mixer| {
+        mixer.music_on = !mixer.music_on;
+        println!("music {}", if mixer.music_on { "on" } else { "off" });
+    });
+}
+
+// raises or lowers the volume by a step
+pub fn change_volume(louder: bool) {
+    with_mixer(|mixer| {
+        let step = if louder { VOLUME_STEP } else { -VOLUME_STEP };
+        mixer.volume = (mixer.volume + step).max(0.).min(1.);
+        mixer.muted = false;
+        println!("volume {:.0} %", mixer.volume * 100.);
+    });
+}
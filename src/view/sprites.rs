@@ -0,0 +1,157 @@
+// optional textures of the boats, the water and the damages, loaded from the sprites directory,
+// the boards are drawn with simple shapes when they are missing or turned off
+use crate::model::{
+    boat::{Class, FLEET},
+    direction::Direction,
+};
+use sdl2::{
+    pixels::Color,
+    rect::Rect,
+    render::{Canvas, Texture, TextureCreator},
+    surface::Surface,
+    video::{Window, WindowContext},
+};
+use std::{
+    cell::{Cell, RefCell},
+    path::Path,
+};
+
+const SPRITE_DIR: &str = "assets/sprites";
+const TRANSPARENT: Color = Color {
+    r: 255,
+    g: 0,
+    b: 255,
+    a: 0,
+};
+
+struct Sprites {
+    boats: Vec<Texture<'static>>, // by class, facing right with a square for each segment
+    water: Texture<'static>,
+    damage: Texture<'static>, // drawn over the wounded segments
+}
+
+thread_local! {
+    // loaded with the first sprite, the error is only printed once
+    static SPRITES: RefCell<Option<Result<Sprites, String>>> = RefCell::new(None);
+    static ENABLED: Cell<bool> = Cell::new(true);
+}
+
+fn load_texture(
+    creator: &'static TextureCreator<WindowContext>,
+    name: &str,
+) -> Result<Texture<'static>, String> {
+    let path = Path::new(SPRITE_DIR).join(format!("{}.bmp", name));
+    let mut surface = Surface::load_bmp(&path)
+        .map_err(|x| format!("Can't load the sprite {} : {}", path.display(), x))?;
+    surface.set_color_key(true, TRANSPARENT)?;
+    creator
+        .create_texture_from_surface(&surface)
+        .map_err(|x| x.to_string())
+}
+
+fn load(canvas: &Canvas<Window>) -> Result<Sprites, String> {
+    let creator: &'static TextureCreator<WindowContext> =
+        Box::leak(Box::new(canvas.texture_creator()));
+    Ok(Sprites {
+        boats: FLEET
+            .iter()
+            .map(|class| load_texture(creator, &class.name().to_lowercase()))
+            .collect::<Result<_, _>>()?,
+        water: load_texture(creator, "water")?,
+        damage: load_texture(creator, "damage")?,
+    })
+}
+
+pub fn toggle_sprites() {
+    ENABLED.with(|enabled| {
+        enabled.set(!enabled.get());
+        println!("sprites {}", if enabled.get() { "on" } else { "off" });
+    });
+}
+
+// draws with the sprites if they are loaded and enabled, returns false otherwise
+fn with_sprites(
+    canvas: &mut Canvas<Window>,
+    draw: impl FnOnce(&mut Canvas<Window>, &Sprites) -> Result<(), String>,
+) -> Result<bool, String> {
+    if !ENABLED.with(|enabled| enabled.get()) {
+        return Ok(false);
+    }
+    SPRITES.with(|cell| {
+        let mut cell = cell.borrow_mut();
+        if cell.is_none() {
+            let loaded = load(canvas);
+            if let Err(e) = &loaded {
+                println!("{}, the boards are drawn without sprites", e);
+            }
+            *cell = Some(loaded);
+        }
+        match cell.as_ref() {
+            Some(Ok(sprites)) => draw(canvas, sprites).map(|_| true),
+            _ => Ok(false),
+        }
+    })
+}
+
+pub trait SpriteRenderer {
+    // the water of the cell whose top left corner is given
+    fn render_water(&mut self, x: i32, y: i32, tile_size: i32) -> Result<bool, String>;
+
+    // a segment of a boat centred on the position, the column is the one of the segment
+    // in the sprite of its class, from the stern to the bow
+    fn render_segment(
+        &mut self,
+        class: Class,
+        column: u8,
+        direction: Direction,
+        center: (i32, i32),
+        tile_size: i32,
+        wounded: bool,
+    ) -> Result<bool, String>;
+}
+
+impl SpriteRenderer for Canvas<Window> {
+    fn render_water(&mut self, x: i32, y: i32, tile_size: i32) -> Result<bool, String> {
+        with_sprites(self, |canvas, sprites| {
+            let size = tile_size as u32;
+            canvas.copy(&sprites.water, None, Some(Rect::new(x, y, size, size)))
+        })
+    }
+
+    fn render_segment(
+        &mut self,
+        class: Class,
+        column: u8,
+        direction: Direction,
+        center: (i32, i32),
+        tile_size: i32,
+        wounded: bool,
+    ) -> Result<bool, String> {
+        with_sprites(self, |canvas, sprites| {
+            let texture = &sprites.boats[class as usize];
+            let cell = texture.query().height;
+            let source = Rect::new(column as i32 * cell as i32, 0, cell, cell);
+            let target = Rect::new(
+                center.0 - tile_size / 2,
+                center.1 - tile_size / 2,
+                tile_size as u32,
+                tile_size as u32,
+            );
+            let (dx, dy) = direction.delta();
+            let angle = (dy as f64).atan2(dx as f64).to_degrees();
+            canvas.copy_ex(
+                texture,
+                Some(source),
+                Some(target),
+                angle,
+                None,
+                false,
+                false,
+            )?;
+            if wounded {
+                canvas.copy(&sprites.damage, None, Some(target))?;
+            }
+            Ok(())
+        })
+    }
+}
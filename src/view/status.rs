@@ -0,0 +1,163 @@
+// status bar below the boards of the game panel
+use crate::model::{
+    boat::FLEET,
+    game::{Game, GameType},
+    notation::format_attack,
+};
+use crate::view::{
+    game::{BOARD_WIDTH, OFFSET_Y},
+    panel::TEXT_COLOR,
+    text::TextRenderer,
+    WIDTH,
+};
+use sdl2::{pixels::Color, rect::Rect, render::Canvas, video::Window};
+
+pub const STATUS_HEIGHT: i32 = 100;
+const STATUS_BACKGROUND: Color = Color {
+    r: 230,
+    g: 230,
+    b: 230,
+    a: 0,
+};
+const TURN_WIDTH: i32 = 200;
+const LAST_SHOT_WIDTH: i32 = 250;
+const ACCURACY_WIDTH: i32 = 200;
+const CONNECTION_WIDTH: i32 = 150;
+// the width of each part of the bar : turn, last shot, fleets, accuracy and connection,
+// the fleets take what the other ones leave
+const COLUMNS: [i32; 5] = [
+    TURN_WIDTH,
+    LAST_SHOT_WIDTH,
+    WIDTH - TURN_WIDTH - LAST_SHOT_WIDTH - ACCURACY_WIDTH - CONNECTION_WIDTH,
+    ACCURACY_WIDTH,
+    CONNECTION_WIDTH,
+];
+
+// whose turn it is, the last shot, the fleets, the accuracy and the connection,
+// seen by the given player of a local game
+fn status(game_type: &GameType, perspective: bool) -> Vec<String> {
+    let (turn, last, stats, connection) = match game_type {
+        GameType::Party { party, .. } => {
+            let turn = if !party.alive[party.id as usize] {
+                "You are eliminated".to_string()
+            } else if party.turn != party.id {
+                format!("Player {}'s turn", party.turn + 1)
+            } else if party.waiting {
+                "Waiting for the results".to_string()
+            } else {
+                "Your turn".to_string()
+            };
+            let last = party.shots.last().map(|&(attacker, attacked, p, shot)| {
+                format!(
+                    "Player {} on player {} : {}",
+                    attacker + 1,
+                    attacked + 1,
+                    format_attack(p, shot)
+                )
+            });
+            let alive = party.alive.iter().filter(|a| **a).count();
+            let connection = format!(
+                "Party of {} players, {} left\n{}",
+                party.alive.len(),
+                alive,
+                if party.is_host() { "Host" } else { "Client" }
+            );
+            (turn, last, party.stats, connection)
+        }
+        _ => {
+            let replay = game_type.replay();
+            let names = match game_type {
+                GameType::Local { .. } if perspective => ["Player 1", "Player 2"],
+                GameType::Local { .. } => ["Player 2", "Player 1"],
+                GameType::Ai { .. } => ["You", "The AI"],
+                _ => ["You", "Your opponent"],
+            };
+            let turn = match game_type {
+                GameType::Local { player, .. } => {
+                    format!("Player {}'s turn", if *player { 1 } else { 2 })
+                }
+                _ if game_type.can_attack() => "Your turn".to_string(),
+                _ => "Opponent's turn".to_string(),
+            };
+            let last = replay.last_attack().map(|(player, p, shot)| {
+                format!(
+                    "{} : {}",
+                    names[(player != perspective) as usize],
+                    format_attack(p, shot)
+                )
+            });
+            let connection = match game_type {
+                GameType::Network { player: true, .. } => "Connected",
+                GameType::Network { .. } => "Connected, waiting for the opponent",
+                GameType::Ai { .. } => "Against the AI",
+                _ => "Hot seat",
+            };
+            (
+                turn,
+                last,
+                replay.stats(perspective),
+                connection.to_string(),
+            )
+        }
+    };
+    vec![
+        turn,
+        format!("Last shot\n{}", last.unwrap_or_else(|| "none".to_string())),
+        fleet(game_type.player_game(perspective), stats.sunk),
+        format!(
+            "{} shots, {} hits\nAccuracy : {} %",
+            stats.shots,
+            stats.hits,
+            stats.accuracy()
+        ),
+        connection,
+    ]
+}
+
+// the health of each boat of the fleet, and the boats sunk on both sides
+fn fleet(game: &Game, sunk: usize) -> String {
+    let boats: Vec<String> = FLEET
+        .iter()
+        .filter_map(|class| game.player.boats.iter().find(|b| b.class == *class))
+        .map(|boat| {
+            let life = boat.detailed_life.iter().filter(|l| **l).count();
+            format!("{} {}/{}", boat.class.name(), life, boat.max_life())
+        })
+        .collect();
+    let lost = game.player.boats.iter().filter(|b| b.is_dead()).count();
+    format!(
+        "Fleet : {}\nBoats sunk : {}, boats lost : {}",
+        boats.join(", "),
+        sunk,
+        lost
+    )
+}
+
+pub fn render_status(
+    canvas: &mut Canvas<Window>,
+    game_type: &GameType,
+    perspective: bool,
+) -> Result<(), String> {
+    canvas.set_draw_color(STATUS_BACKGROUND);
+    canvas.fill_rect(Rect::new(
+        0,
+        OFFSET_Y + BOARD_WIDTH,
+        WIDTH as u32,
+        STATUS_HEIGHT as u32,
+    ))?;
+    let mut x = 0;
+    for (text, &width) in status(game_type, perspective).iter().zip(COLUMNS.iter()) {
+        canvas.render_text(
+            text,
+            TEXT_COLOR,
+            Rect::new(
+                x,
+                OFFSET_Y + BOARD_WIDTH,
+                width as u32,
+                STATUS_HEIGHT as u32,
+            ),
+        )?;
+        x += width;
+    }
+    Ok(())
+}
@@ -0,0 +1,129 @@
+// text rendering with the bundled font, the texture of each text is kept to be drawn again,
+// the font is loaded again at the scale of the window to keep the texts sharp
+use sdl2::{
+    pixels::Color,
+    rect::Rect,
+    render::{Canvas, Texture, TextureCreator},
+    rwops::RWops,
+    ttf::{self, Font, Sdl2TtfContext},
+    video::{Window, WindowContext},
+};
+use std::{cell::RefCell, collections::HashMap};
+
+// in the executable, to display the texts wherever it is run from
+const FONT: &[u8] = include_bytes!("../../assets/DejaVuSans.ttf");
+const FONT_SIZE: u16 = 16;
+const MARGIN: u32 = 4;
+// the cache is emptied beyond this number of textures, for the texts typed by the player
+const MAX_TEXTURES: usize = 256;
+
+// the ttf context and the texture creator are kept until the end of the process
+struct Text {
+    context: &'static Sdl2TtfContext,
+    font: Font<'static, 'static>,
+    scale: f32, // the one of the canvas when the font was loaded
+    creator: &'static TextureCreator<WindowContext>,
+    textures: HashMap<(String, u32, u32), Texture<'static>>, // by text, color and wrap width
+}
+
+thread_local! {
+    // loaded with the first text, the error is only printed once
+    static TEXT: RefCell<Option<Result<Text, String>>> = RefCell::new(None);
+}
+
+fn load_font(
+    context: &'static Sdl2TtfContext,
+    scale: f32,
+) -> Result<Font<'static, 'static>, String> {
+    let size = ((FONT_SIZE as f32 * scale).round() as u16).max(1);
+    RWops::from_bytes(FONT)
+        .and_then(|rwops| context.load_font_from_rwops(rwops, size))
+        .map_err(|x| format!("Can't load the font : {}", x))
+}
+
+fn load(canvas: &Canvas<Window>) -> Result<Text, String> {
+    let context = ttf::init().map_err(|x| x.to_string())?;
+    let context = Box::leak(Box::new(context));
+    let scale = canvas.scale().0;
+    Ok(Text {
+        context,
+        font: load_font(context, scale)?,
+        scale,
+        creator: Box::leak(Box::new(canvas.texture_creator())),
+        textures: HashMap::new(),
+    })
+}
+
+fn color_key(color: Color) -> u32 {
+    u32::from_be_bytes([color.r, color.g, color.b, color.a])
+}
+
+pub trait TextRenderer {
+    // draws the text centred in the rectangle, on several lines if it's too wide,
+    // and shrunk if it's still too big
+    fn render_text(&mut self, text: &str, color: Color, rect: Rect) -> Result<(), String>;
+}
+
+impl TextRenderer for Canvas<Window> {
+    fn render_text(&mut self, text: &str, color: Color, rect: Rect) -> Result<(), String> {
+        if text.is_empty() || rect.width() <= 2 * MARGIN || rect.height() <= 2 * MARGIN {
+            return Ok(());
+        }
+        TEXT.with(|cell| {
+            let mut cell = cell.borrow_mut();
+            if cell.is_none() {
+                let loaded = load(self);
+                if let Err(e) = &loaded {
+                    println!("{}, the texts won't be displayed", e);
+                }
+                *cell = Some(loaded);
+            }
+            let text_data = match cell.as_mut() {
+                Some(Ok(text_data)) => text_data,
+                _ => return Ok(()),
+            };
+
+            let scale = self.scale().0;
+            if scale != text_data.scale {
+                text_data.font = load_font(text_data.context, scale)?;
+                text_data.scale = scale;
+                text_data.textures.clear();
+            }
+
+            // the textures are in pixels of the window, the rectangle is scaled by the canvas
+            let width = ((rect.width() - 2 * MARGIN) as f32 * scale) as u32;
+            let key = (text.to_string(), color_key(color), width);
+            if !text_data.textures.contains_key(&key) {
+                if text_data.textures.len() >= MAX_TEXTURES {
+                    text_data.textures.clear();
+                }
+                let surface = text_data
+                    .font
+                    .render(text)
+                    .blended_wrapped(color, width)
+                    .map_err(|x| x.to_string())?;
+                let texture = text_data
+                    .creator
+                    .create_texture_from_surface(&surface)
+                    .map_err(|x| x.to_string())?;
+                text_data.textures.insert(key.clone(), texture);
+            }
+            let texture = &text_data.textures[&key];
+
+            let query = texture.query();
+            let (w, h) = (query.width as f32 / scale, query.height as f32 / scale);
+            let width = rect.width() - 2 * MARGIN;
+            let scale = (width as f32 / w)
+                .min((rect.height() - 2 * MARGIN) as f32 / h)
+                .min(1.);
+            let (w, h) = ((w * scale) as u32, (h * scale) as u32);
+            let target = Rect::new(
+                rect.x() + (rect.width() - w) as i32 / 2,
+                rect.y() + (rect.height() - h) as i32 / 2,
+                w,
+                h,
+            );
+            self.copy(texture, None, Some(target))
+        })
+    }
+}
@@ -0,0 +1,141 @@
+// the colours of the boards, with palettes for the colour blind players,
+// the shapes of the marks still tell the hits from the misses whatever the palette
+use sdl2::pixels::Color;
+use std::{cell::Cell, env};
+
+const THEME_VARIABLE: &str = "BATTLESHIP_THEME";
+
+#[derive(Copy, Clone, Debug)]
+pub struct Theme {
+    pub name: &'static str,
+    pub healthy_boat: Color,
+    pub wounded_boat: Color, // also the hits on the shot board
+    pub miss: Color,
+    pub revealed: Color, // the cells known to be occupied
+    pub island: Color,
+    pub mine: Color,
+    pub valid: Color, // the cells where a boat can be placed or which can be attacked
+    pub invalid: Color,
+    pub selection: Color, // the selected boat and the targets of a salvo
+    pub ability: Color,   // the area of an ability
+    // the animations of the shots
+    pub background: Color, // of the game panel, hides a shot until the projectile lands
+    pub projectile: Color,
+    pub splash: Color,
+    pub foam: Color, // the inside of a splash and the bubbles of a sinking boat
+    pub explosion: Color,
+    pub flame: Color, // the inside of an explosion
+    pub water: Color, // rising over a sunk boat, without the water sprite
+}
+
+const fn rgb(r: u8, g: u8, b: u8) -> Color {
+    Color { r, g, b, a: 0 }
+}
+
+pub const THEMES: [Theme; 4] = [
+    Theme {
+        name: "Classic",
+        healthy_boat: rgb(0, 0, 255),
+        wounded_boat: rgb(255, 0, 0),
+        miss: rgb(0, 0, 255),
+        revealed: rgb(255, 0, 255),
+        island: rgb(194, 178, 128),
+        mine: rgb(0, 0, 0),
+        valid: rgb(0, 255, 0),
+        invalid: rgb(255, 0, 0),
+        selection: rgb(255, 255, 0),
+        ability: rgb(255, 0, 255),
+        background: rgb(255, 255, 255),
+        projectile: rgb(0, 0, 0),
+        splash: rgb(0, 0, 255),
+        foam: rgb(0, 255, 255),
+        explosion: rgb(255, 140, 0),
+        flame: rgb(255, 255, 0),
+        water: rgb(30, 80, 160),
+    },
+    // the Okabe-Ito colours, told apart without red and green
+    Theme {
+        name: "Red-green safe",
+        healthy_boat: rgb(0, 114, 178),
+        wounded_boat: rgb(213, 94, 0),
+        miss: rgb(86, 180, 233),
+        revealed: rgb(204, 121, 167),
+        island: rgb(194, 178, 128),
+        mine: rgb(0, 0, 0),
+        valid: rgb(0, 114, 178),
+        invalid: rgb(230, 159, 0),
+        selection: rgb(240, 228, 66),
+        ability: rgb(204, 121, 167),
+        background: rgb(255, 255, 255),
+        projectile: rgb(0, 0, 0),
+        splash: rgb(0, 114, 178),
+        foam: rgb(86, 180, 233),
+        explosion: rgb(213, 94, 0),
+        flame: rgb(240, 228, 66),
+        water: rgb(0, 70, 110),
+    },
+    // without blue and yellow
+    Theme {
+        name: "Blue-yellow safe",
+        healthy_boat: rgb(0, 77, 64),
+        wounded_boat: rgb(220, 50, 32),
+        miss: rgb(120, 120, 120),
+        revealed: rgb(255, 105, 180),
+        island: rgb(170, 170, 170),
+        mine: rgb(0, 0, 0),
+        valid: rgb(0, 77, 64),
+        invalid: rgb(220, 50, 32),
+        selection: rgb(255, 105, 180),
+        ability: rgb(128, 0, 128),
+        background: rgb(255, 255, 255),
+        projectile: rgb(0, 0, 0),
+        splash: rgb(0, 77, 64),
+        foam: rgb(120, 120, 120),
+        explosion: rgb(220, 50, 32),
+        flame: rgb(255, 105, 180),
+        water: rgb(60, 60, 60),
+    },
+    Theme {
+        name: "High contrast",
+        healthy_boat: rgb(0, 0, 0),
+        wounded_boat: rgb(200, 0, 0),
+        miss: rgb(0, 0, 0),
+        revealed: rgb(0, 0, 0),
+        island: rgb(128, 128, 128),
+        mine: rgb(0, 0, 0),
+        valid: rgb(0, 0, 0),
+        invalid: rgb(200, 0, 0),
+        selection: rgb(0, 0, 255),
+        ability: rgb(0, 0, 255),
+        background: rgb(255, 255, 255),
+        projectile: rgb(0, 0, 0),
+        splash: rgb(0, 0, 0),
+        foam: rgb(128, 128, 128),
+        explosion: rgb(200, 0, 0),
+        flame: rgb(255, 255, 255),
+        water: rgb(0, 0, 255),
+    },
+];
+
+thread_local! {
+    // the index of the current theme, chosen by the environment variable at first
+    static THEME: Cell<usize> = Cell::new(match env::var(THEME_VARIABLE) {
+        Ok(name) => THEMES
+            .iter()
+            .position(|t| t.name.eq_ignore_ascii_case(&name))
+            .unwrap_or_else(|| {
+                println!("Unknown theme '{}', the classic one is used", name);
+                0
+            }),
+        Err(_) => 0,
+    });
+}
+
+pub fn theme() -> Theme {
+    THEMES[THEME.with(|t| t.get())]
+}
+
+pub fn next_theme() {
+    THEME.with(|t| t.set((t.get() + 1) % THEMES.len()));
+    println!("theme : {}", theme().name);
+}